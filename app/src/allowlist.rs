@@ -0,0 +1,328 @@
+//! Voter allowlists.
+//!
+//! A ballot can be deployed with the Merkle root of the addresses allowed to vote, and then only
+//! accepts votes that come with a proof of membership. [`Allowlist`] builds the tree from a voter
+//! roll, gives the root to deploy with, and holds a proof for every voter, so the organiser can
+//! publish one file that every voter's client picks its own proof out of.
+//!
+//! The tree hashes like the contract's `isEligible`: each leaf is
+//! `keccak256(keccak256(abi.encode(voter)))`, and each pair of nodes is hashed in sorted order,
+//! as OpenZeppelin's `MerkleProof` expects. The tree is laid out like OpenZeppelin's
+//! `StandardMerkleTree`, so its root is the one `StandardMerkleTree.of(voters, ["address"])`
+//! gives for the same voters. [`verify_proof`] repeats the contract's check, so a proof that
+//! passes here passes on chain.
+
+use std::{collections::BTreeMap, str::FromStr, sync::Arc};
+
+use ethers::{
+    abi::{self, Token},
+    types::{Address, H256},
+    utils::keccak256,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::BallotError;
+
+fn leaf(voter: Address) -> H256 {
+    keccak256(keccak256(abi::encode(&[Token::Address(voter)]))).into()
+}
+
+fn hash_pair(a: H256, b: H256) -> H256 {
+    let (a, b) = if a <= b { (a, b) } else { (b, a) };
+    keccak256([a.as_bytes(), b.as_bytes()].concat()).into()
+}
+
+/// Build the tree over `leaves`, sorted, in OpenZeppelin's `StandardMerkleTree` layout: an array
+/// with the root first, the children of node `i` at `2i + 1` and `2i + 2`, and the leaves at the
+/// end in reverse order.
+fn tree(leaves: &[H256]) -> Vec<H256> {
+    let mut tree = vec![H256::zero(); 2 * leaves.len() - 1];
+    let last = tree.len() - 1;
+    for (index, &leaf) in leaves.iter().enumerate() {
+        tree[last - index] = leaf;
+    }
+    for node in (0..leaves.len() - 1).rev() {
+        tree[node] = hash_pair(tree[2 * node + 1], tree[2 * node + 2]);
+    }
+    tree
+}
+
+/// The proof for the `index`th leaf of a [`tree`].
+fn tree_proof(tree: &[H256], index: usize) -> Vec<H256> {
+    let mut node = tree.len() - 1 - index;
+    let mut proof = Vec::new();
+    while node > 0 {
+        let sibling = if node % 2 == 1 { node + 1 } else { node - 1 };
+        proof.push(tree[sibling]);
+        node = (node - 1) / 2;
+    }
+    proof
+}
+
+/// Check `proof` for `voter` against `root`, exactly as the contract does.
+///
+/// A zero root means the ballot has no allowlist, so everyone passes.
+pub fn verify_proof(root: H256, voter: Address, proof: &[H256]) -> bool {
+    root.is_zero()
+        || proof.iter().fold(leaf(voter), |node, &sibling| hash_pair(node, sibling)) == root
+}
+
+/// A voter roll entry, in either of the JSON shapes accepted.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RollEntry {
+    Address(Address),
+    Voter { address: Address },
+}
+
+/// The published form of an [`Allowlist`].
+#[derive(Serialize, Deserialize)]
+struct AllowlistFile {
+    root: H256,
+    voters: Vec<VoterProof>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct VoterProof {
+    address: Address,
+    proof: Vec<H256>,
+}
+
+/// The addresses allowed to vote on a ballot, with a Merkle proof for each.
+#[derive(Debug, uniffi::Object)]
+pub struct Allowlist {
+    root: H256,
+    proofs: BTreeMap<Address, Vec<H256>>,
+}
+
+impl Allowlist {
+    /// Build the tree over `voters`. Duplicates are ignored; an empty roll is an error.
+    pub fn new(voters: impl IntoIterator<Item = Address>) -> Result<Self, BallotError> {
+        let mut leaves: Vec<_> = voters.into_iter().map(|voter| (leaf(voter), voter)).collect();
+        leaves.sort();
+        leaves.dedup();
+        if leaves.is_empty() {
+            return Err(BallotError::allowlist("the voter roll is empty"));
+        }
+
+        let leaf_hashes: Vec<_> = leaves.iter().map(|&(leaf, _)| leaf).collect();
+        let tree = tree(&leaf_hashes);
+        let root = tree[0];
+        let proofs = leaves
+            .iter()
+            .enumerate()
+            .map(|(index, &(_, voter))| (voter, tree_proof(&tree, index)))
+            .collect();
+        Ok(Self { root, proofs })
+    }
+
+    /// The root to deploy the ballot with.
+    pub fn root_hash(&self) -> H256 {
+        self.root
+    }
+
+    pub fn proof(&self, voter: Address) -> Option<&[H256]> {
+        self.proofs.get(&voter).map(Vec::as_slice)
+    }
+
+    /// The proof `voter` needs for a ballot with voter root `root`, checked locally first.
+    ///
+    /// Ballots without an allowlist (a zero root) take an empty proof. Otherwise this fails if
+    /// the ballot was deployed with a different allowlist, or `voter` isn't on this one.
+    pub fn proof_for(&self, root: H256, voter: Address) -> Result<Vec<H256>, BallotError> {
+        if root.is_zero() {
+            return Ok(Vec::new());
+        }
+        if root != self.root {
+            return Err(BallotError::allowlist(format!(
+                "the ballot's voter root is {root:?}, but this allowlist's is {:?}",
+                self.root
+            )));
+        }
+        match self.proof(voter) {
+            Some(proof) if verify_proof(root, voter, proof) => Ok(proof.to_vec()),
+            Some(_) => Err(BallotError::allowlist(format!("the proof for {voter:?} is invalid"))),
+            None => Err(BallotError::not_eligible(format!("{voter:?} is not on the allowlist"))),
+        }
+    }
+}
+
+/// The proof `voter` needs on a ballot with voter root `root`, from whichever of `allowlists`
+/// the ballot was deployed with.
+pub fn voter_proof<'a>(
+    root: H256,
+    voter: Address,
+    allowlists: impl IntoIterator<Item = &'a Allowlist>,
+) -> Result<Vec<H256>, BallotError> {
+    if root.is_zero() {
+        return Ok(Vec::new());
+    }
+    allowlists
+        .into_iter()
+        .find(|allowlist| allowlist.root == root)
+        .ok_or_else(|| {
+            BallotError::not_eligible(format!(
+                "the ballot only accepts voters on the allowlist with root {root:?}"
+            ))
+        })?
+        .proof_for(root, voter)
+}
+
+#[uniffi::export]
+impl Allowlist {
+    /// Build an allowlist from a voter roll: a JSON array of addresses or of objects with an
+    /// `address` field, or CSV with the address in the first column and an optional header row.
+    #[uniffi::constructor]
+    pub fn from_voter_roll(contents: String) -> Result<Arc<Self>, BallotError> {
+        let voters = if contents.trim_start().starts_with('[') {
+            let entries: Vec<RollEntry> =
+                serde_json::from_str(&contents).map_err(BallotError::allowlist)?;
+            entries
+                .into_iter()
+                .map(|entry| match entry {
+                    RollEntry::Address(address) | RollEntry::Voter { address } => address,
+                })
+                .collect()
+        } else {
+            parse_csv(&contents)?
+        };
+        Ok(Arc::new(Self::new(voters)?))
+    }
+
+    /// Read an allowlist published with [`Allowlist::to_json`]. Every proof is checked against
+    /// the root.
+    #[uniffi::constructor]
+    pub fn from_json(json: String) -> Result<Arc<Self>, BallotError> {
+        let file: AllowlistFile = serde_json::from_str(&json).map_err(BallotError::allowlist)?;
+        if file.root.is_zero() {
+            return Err(BallotError::allowlist("the root is zero"));
+        }
+        if let Some(voter) =
+            file.voters.iter().find(|voter| !verify_proof(file.root, voter.address, &voter.proof))
+        {
+            return Err(BallotError::allowlist(format!(
+                "the proof for {:?} doesn't match the root",
+                voter.address
+            )));
+        }
+        let proofs = file.voters.into_iter().map(|voter| (voter.address, voter.proof)).collect();
+        Ok(Arc::new(Self { root: file.root, proofs }))
+    }
+
+    /// The root to deploy the ballot with, as `0x` prefixed hex.
+    pub fn root(&self) -> String {
+        format!("{:?}", self.root)
+    }
+
+    pub fn voters(&self) -> Vec<String> {
+        self.proofs.keys().map(|voter| format!("{voter:?}")).collect()
+    }
+
+    pub fn contains(&self, voter: String) -> Result<bool, BallotError> {
+        let voter = Address::from_str(&voter).map_err(BallotError::address)?;
+        Ok(self.proofs.contains_key(&voter))
+    }
+
+    /// The allowlist as JSON, for the organiser to publish to voters.
+    pub fn to_json(&self) -> Result<String, BallotError> {
+        let voters = self
+            .proofs
+            .iter()
+            .map(|(&address, proof)| VoterProof { address, proof: proof.clone() })
+            .collect();
+        let file = AllowlistFile { root: self.root, voters };
+        serde_json::to_string_pretty(&file).map_err(BallotError::serialization)
+    }
+}
+
+/// Addresses from the first column of a CSV voter roll, skipping a header row if there is one.
+fn parse_csv(contents: &str) -> Result<Vec<Address>, BallotError> {
+    let mut voters = Vec::new();
+    for (number, line) in contents.lines().enumerate() {
+        let field = line.split(',').next().unwrap_or_default().trim().trim_matches('"');
+        if field.is_empty() {
+            continue;
+        }
+        match Address::from_str(field) {
+            Ok(voter) => voters.push(voter),
+            Err(_) if voters.is_empty() && number == 0 => continue,
+            Err(e) => {
+                return Err(BallotError::allowlist(format!("line {}: {field:?}: {e}", number + 1)))
+            }
+        }
+    }
+    Ok(voters)
+}
+
+#[cfg(test)]
+mod tests {
+    use ethers::types::U256;
+
+    use super::*;
+
+    fn voters(count: u8) -> Vec<Address> {
+        (1..=count).map(|byte| Address::repeat_byte(byte * 0x11)).collect()
+    }
+
+    /// The two entry `(address, uint256)` tree from the `@openzeppelin/merkle-tree` README, to
+    /// check leaf and pair hashing against the library itself.
+    #[test]
+    fn matches_openzeppelin_readme_root() {
+        let leaf = |voter: Address, amount: U256| -> H256 {
+            let encoded = abi::encode(&[Token::Address(voter), Token::Uint(amount)]);
+            keccak256(keccak256(encoded)).into()
+        };
+        let mut leaves = vec![
+            leaf(Address::repeat_byte(0x11), U256::exp10(18) * 5),
+            leaf(Address::repeat_byte(0x22), U256::exp10(17) * 25),
+        ];
+        leaves.sort();
+        let root: H256 =
+            "0xd4dee0beab2d53f2cc83e567171bd2820e49898130a22622b10ead383e90bd77".parse().unwrap();
+        assert_eq!(tree(&leaves)[0], root);
+    }
+
+    /// With five leaves the layout matters: `StandardMerkleTree` pairs the odd leaf out with a
+    /// node one level up rather than carrying it to the top.
+    #[test]
+    fn matches_standard_merkle_tree_layout() {
+        let allowlist = Allowlist::new(voters(5)).unwrap();
+        let root: H256 =
+            "0xd21ca6424df344c7bdcd3d65c364ffa0cfbe7e04510d81fa57c175716da06376".parse().unwrap();
+        assert_eq!(allowlist.root_hash(), root);
+    }
+
+    #[test]
+    fn every_proof_verifies() {
+        for count in 1..=9 {
+            let allowlist = Allowlist::new(voters(count)).unwrap();
+            for voter in voters(count) {
+                let proof = allowlist.proof_for(allowlist.root_hash(), voter).unwrap();
+                assert!(verify_proof(allowlist.root_hash(), voter, &proof));
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_outsiders_and_other_roots() {
+        let allowlist = Allowlist::new(voters(3)).unwrap();
+        let outsider = Address::repeat_byte(0xee);
+        assert!(matches!(
+            allowlist.proof_for(allowlist.root_hash(), outsider),
+            Err(BallotError::NotEligible { .. })
+        ));
+        assert!(matches!(
+            allowlist.proof_for(H256::repeat_byte(1), voters(1)[0]),
+            Err(BallotError::Allowlist { .. })
+        ));
+        assert_eq!(allowlist.proof_for(H256::zero(), outsider).unwrap(), Vec::<H256>::new());
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let allowlist = Allowlist::new(voters(4)).unwrap();
+        let parsed = Allowlist::from_json(allowlist.to_json().unwrap()).unwrap();
+        assert_eq!(parsed.root_hash(), allowlist.root_hash());
+        assert_eq!(parsed.voters(), allowlist.voters());
+    }
+}
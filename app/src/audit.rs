@@ -0,0 +1,139 @@
+//! Auditing cast votes from their calldata.
+//!
+//! The ballot adds up whatever ciphertexts a `vote`, `changeVote` or `voteBySig` call carries.
+//! Anyone can check, without a key, that a call carries the ciphertexts the ballot counts: one
+//! per proposal, or one packed count, each of the type the ballot adds up and under the
+//! parameters the client runtime uses. That catches malformed votes, but says nothing about the
+//! plaintexts: a well-formed encryption of a vote of a million still passes. Ruling those out
+//! takes a proof linked to the ciphertexts, which Sunscreen 0.8 has no way to produce.
+
+use bindings::ballot::BallotCalls;
+use ethers::{abi::AbiDecode, types::Bytes};
+use sunscreen::{
+    types::{
+        bfv::{Unsigned256, Unsigned64},
+        Cipher, Type, TypeName,
+    },
+    Ciphertext, InnerCiphertext,
+};
+use sunscreen_web3::{testnet::parasol::RUNTIME, AsBytes};
+
+use crate::{params, BallotError};
+
+/// What the calldata of one vote says about it.
+#[derive(Clone, Debug)]
+pub struct VoteAudit {
+    /// The function called: `vote`, `changeVote` or `voteBySig`
+    pub function: String,
+    /// The voter who signed a `voteBySig`, as lower case hex without a `0x` prefix; `None` for
+    /// the others, whose voter is whoever sent the transaction
+    pub voter: Option<String>,
+    /// One line per way the votes are malformed; empty if they're well-formed
+    pub problems: Vec<String>,
+}
+
+impl VoteAudit {
+    pub fn is_well_formed(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+/// Check the calldata of a vote on a ballot with `proposal_count` proposals, packed into
+/// `slot_bits` wide slots, or unpacked if `slot_bits` is 0.
+///
+/// Fails if `calldata` isn't a call to one of the ballot's vote functions.
+pub fn verify_vote_call(
+    calldata: &[u8],
+    proposal_count: usize,
+    slot_bits: u8,
+) -> Result<VoteAudit, BallotError> {
+    let (function, voter, votes) = match BallotCalls::decode(calldata)
+        .map_err(BallotError::invalid_vote)?
+    {
+        BallotCalls::Vote(call) => ("vote", None, call.votes),
+        BallotCalls::ChangeVote(call) => ("changeVote", None, call.votes),
+        BallotCalls::VoteBySig(call) => {
+            ("voteBySig", Some(format!("{:x}", call.voter)), call.votes)
+        }
+        _ => return Err(BallotError::invalid_vote("not a call to vote, changeVote or voteBySig")),
+    };
+
+    let (count, data_type) = match slot_bits {
+        0 => (proposal_count, Cipher::<Unsigned64>::type_name()),
+        _ => (1, Cipher::<Unsigned256>::type_name()),
+    };
+    let mut problems = Vec::new();
+    if votes.len() != count {
+        problems.push(format!("{} ciphertexts, where the ballot counts {count}", votes.len()));
+    }
+    for (i, vote) in votes.iter().enumerate() {
+        let vote_problems = check_ciphertext(vote, &data_type);
+        problems.extend(vote_problems.into_iter().map(|problem| format!("vote {i}: {problem}")));
+    }
+
+    Ok(VoteAudit { function: function.into(), voter, problems })
+}
+
+/// Ways `bytes` isn't a ciphertext of `data_type` under the runtime's parameters.
+fn check_ciphertext(bytes: &Bytes, data_type: &Type) -> Vec<String> {
+    let ciphertext = match Ciphertext::from_bytes(bytes) {
+        Ok(ciphertext) => ciphertext,
+        Err(e) => return vec![format!("not a ciphertext: {e}")],
+    };
+
+    let mut problems = Vec::new();
+    if ciphertext.data_type != *data_type {
+        problems.push(format!(
+            "encrypts a {}, where the ballot adds up {}",
+            ciphertext.data_type.name, data_type.name
+        ));
+    }
+    let InnerCiphertext::Seal(parts) = &ciphertext.inner;
+    for part in parts {
+        problems.extend(params::diff(("runtime", RUNTIME.params()), ("ciphertext", &part.params)));
+    }
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use bindings::ballot::{AddProposalCall, VoteCall};
+    use ethers::abi::AbiEncode;
+    use sunscreen_web3::testnet::parasol::generate_keys;
+
+    use super::*;
+    use crate::EncVotes;
+
+    fn vote_calldata(votes: &[u64], slot_bits: u8) -> Vec<u8> {
+        let (public_key, _) = generate_keys().unwrap();
+        let votes = EncVotes::encrypt(votes, slot_bits, &public_key).unwrap().into_bytes();
+        VoteCall { votes, proof: Vec::new() }.encode()
+    }
+
+    #[test]
+    fn accepts_well_formed_votes() {
+        let audit = verify_vote_call(&vote_calldata(&[1, 0], 0), 2, 0).unwrap();
+        assert!(audit.is_well_formed(), "{:?}", audit.problems);
+        assert_eq!(audit.function, "vote");
+
+        let audit = verify_vote_call(&vote_calldata(&[1, 0], 8), 2, 8).unwrap();
+        assert!(audit.is_well_formed(), "{:?}", audit.problems);
+    }
+
+    #[test]
+    fn flags_malformed_votes() {
+        // One vote short
+        assert!(!verify_vote_call(&vote_calldata(&[1], 0), 2, 0).unwrap().is_well_formed());
+        // Per proposal votes on a packed ballot
+        assert!(!verify_vote_call(&vote_calldata(&[1], 0), 1, 8).unwrap().is_well_formed());
+
+        let garbage = VoteCall { votes: vec![Bytes::from(vec![0; 32])], proof: Vec::new() };
+        assert!(!verify_vote_call(&garbage.encode(), 1, 0).unwrap().is_well_formed());
+    }
+
+    #[test]
+    fn rejects_other_calls() {
+        let call = AddProposalCall { name: "a".into(), contents: String::new() };
+        assert!(verify_vote_call(&call.encode(), 1, 0).is_err());
+    }
+}
@@ -0,0 +1,235 @@
+//! The contract surface the client talks to.
+//!
+//! [`BallotBackend`] mirrors the functions of the `Ballot` contract. The deployed contract
+//! implements it through the generated bindings, and [`MockBallot`](crate::MockBallot)
+//! implements it in memory, so everything above this layer runs the same with or without a
+//! node.
+
+use async_trait::async_trait;
+use bindings::ballot::{Ballot, Proposal};
+use ethers::{
+    abi::parse_abi,
+    contract::Contract,
+    providers::Middleware,
+    types::{Address, Bytes, H256},
+};
+use futures::{stream::BoxStream, StreamExt};
+
+use crate::{events, BallotError, BallotEvent, EncUint256, EncVotes, Phase, SignedVote, TxOutcome};
+
+/// A deployed ballot, on chain or otherwise.
+///
+/// Transactions wait to be mined and return their [`TxOutcome`]; backends without a chain make
+/// one up.
+#[async_trait]
+pub trait BallotBackend: Send + Sync {
+    /// Address of the ballot.
+    fn address(&self) -> Address;
+
+    /// Id of the chain the ballot is on, which [signed votes](SignedVote) are bound to.
+    async fn chain_id(&self) -> Result<u64, BallotError>;
+
+    /// The network public key votes are encrypted under.
+    async fn get_public_key(&self) -> Result<Bytes, BallotError>;
+
+    async fn get_proposals(&self) -> Result<Vec<Proposal>, BallotError>;
+
+    async fn has_voted(&self, voter: Address) -> Result<bool, BallotError>;
+
+    /// How many signed votes `voter` has cast on the ballot, which is the nonce the next one
+    /// must be signed with.
+    async fn nonce(&self, voter: Address) -> Result<u64, BallotError>;
+
+    async fn phase(&self) -> Result<Phase, BallotError>;
+
+    /// Merkle root of the voter [allowlist](crate::Allowlist), or zero if anyone can vote.
+    async fn voter_root(&self) -> Result<H256, BallotError>;
+
+    /// When voting ends, as a Unix timestamp; 0 until voting opens.
+    async fn voting_ends(&self) -> Result<u64, BallotError>;
+
+    /// Bits per proposal if the ballot is [packed](crate::Packing), or 0 if every proposal keeps
+    /// its own count.
+    async fn slot_bits(&self) -> Result<u8, BallotError>;
+
+    /// The packed count of a packed ballot; empty otherwise.
+    async fn packed_count(&self) -> Result<Bytes, BallotError>;
+
+    /// The plaintext tally of each proposal. Fails until voting has closed.
+    async fn get_results(&self) -> Result<Vec<u64>, BallotError>;
+
+    /// Every tally, re-encrypted under `reenc_public_key`; just the packed count on a packed
+    /// ballot.
+    async fn get_proposal_tallys(
+        &self,
+        reenc_public_key: Bytes,
+    ) -> Result<Vec<EncUint256>, BallotError>;
+
+    /// Add a proposal. Only the organiser can, and only during setup.
+    async fn add_proposal(&self, name: String, contents: String) -> Result<TxOutcome, BallotError>;
+
+    /// Add several `(name, contents)` proposals in one transaction, which adds all of them or
+    /// none. Only the organiser can, and only during setup.
+    async fn add_proposals(
+        &self,
+        proposals: Vec<(String, String)>,
+    ) -> Result<TxOutcome, BallotError>;
+
+    /// Cast encrypted votes, laid out as the ballot counts them, replacing any earlier vote from
+    /// the same sender. `proof` shows the sender is on the voter allowlist, and is empty if there
+    /// is none.
+    async fn vote(&self, votes: EncVotes, proof: Vec<H256>) -> Result<TxOutcome, BallotError>;
+
+    /// Like [`BallotBackend::vote`], but fails if the sender hasn't voted yet.
+    async fn change_vote(
+        &self,
+        votes: EncVotes,
+        proof: Vec<H256>,
+    ) -> Result<TxOutcome, BallotError>;
+
+    /// Cast votes signed by their voter, like [`BallotBackend::vote`] from the voter, but with
+    /// the sender paying for it.
+    async fn vote_by_sig(&self, vote: SignedVote) -> Result<TxOutcome, BallotError>;
+
+    /// Stop adding proposals and accept votes until `voting_ends`, a Unix timestamp. Only the
+    /// organiser, who deployed the ballot, can open it.
+    async fn open(&self, voting_ends: u64) -> Result<TxOutcome, BallotError>;
+
+    /// Stop accepting votes and publish the results. The organiser can close voting at any
+    /// time, anyone else only once it has ended.
+    async fn close(&self) -> Result<TxOutcome, BallotError>;
+
+    /// Stream the ballot's events, starting with any emitted since `from_block`.
+    async fn events(
+        &self,
+        from_block: Option<u64>,
+    ) -> Result<BoxStream<'_, Result<BallotEvent, BallotError>>, BallotError>;
+}
+
+#[async_trait]
+impl<M: Middleware + 'static> BallotBackend for Ballot<M> {
+    fn address(&self) -> Address {
+        (**self).address()
+    }
+
+    async fn chain_id(&self) -> Result<u64, BallotError> {
+        let chain_id = self.client().get_chainid().await.map_err(BallotError::rpc)?;
+        u64::try_from(chain_id).map_err(|_| BallotError::rpc("chain id is out of range"))
+    }
+
+    async fn get_public_key(&self) -> Result<Bytes, BallotError> {
+        Ok(Ballot::get_public_key(self).call().await?)
+    }
+
+    async fn get_proposals(&self) -> Result<Vec<Proposal>, BallotError> {
+        Ok(Ballot::get_proposals(self).call().await?)
+    }
+
+    async fn has_voted(&self, voter: Address) -> Result<bool, BallotError> {
+        Ok(Ballot::has_voted(self, voter).call().await?)
+    }
+
+    async fn nonce(&self, voter: Address) -> Result<u64, BallotError> {
+        let nonce = Ballot::nonces(self, voter).call().await?;
+        u64::try_from(nonce).map_err(|_| BallotError::rpc("nonce is out of range"))
+    }
+
+    async fn phase(&self) -> Result<Phase, BallotError> {
+        Ballot::phase(self).call().await?.try_into()
+    }
+
+    async fn voter_root(&self) -> Result<H256, BallotError> {
+        Ok(Ballot::voter_root(self).call().await?.into())
+    }
+
+    async fn voting_ends(&self) -> Result<u64, BallotError> {
+        let voting_ends = Ballot::voting_ends(self).call().await?;
+        u64::try_from(voting_ends).map_err(|_| BallotError::rpc("voting end is out of range"))
+    }
+
+    async fn slot_bits(&self) -> Result<u8, BallotError> {
+        Ok(Ballot::slot_bits(self).call().await?)
+    }
+
+    async fn packed_count(&self) -> Result<Bytes, BallotError> {
+        Ok(Ballot::packed_count(self).call().await?)
+    }
+
+    async fn get_results(&self) -> Result<Vec<u64>, BallotError> {
+        Ok(Ballot::get_results(self).call().await?)
+    }
+
+    async fn get_proposal_tallys(
+        &self,
+        reenc_public_key: Bytes,
+    ) -> Result<Vec<EncUint256>, BallotError> {
+        let tallys = Ballot::get_proposal_tallys(self, reenc_public_key).call().await?;
+        Ok(tallys.into_iter().map(EncUint256::from).collect())
+    }
+
+    async fn add_proposal(&self, name: String, contents: String) -> Result<TxOutcome, BallotError> {
+        let call = Ballot::add_proposal(self, name, contents);
+        let pending = call.send().await?;
+        TxOutcome::confirm(self.client_ref(), pending).await
+    }
+
+    async fn add_proposals(
+        &self,
+        proposals: Vec<(String, String)>,
+    ) -> Result<TxOutcome, BallotError> {
+        let (names, contents): (Vec<_>, Vec<_>) = proposals.into_iter().unzip();
+        // Called by signature until the bindings are regenerated with `addProposals`
+        let abi = parse_abi(&["function addProposals(string[] names, string[] contents)"])
+            .map_err(BallotError::serialization)?;
+        let call = Contract::new(BallotBackend::address(self), abi, self.client())
+            .method::<_, ()>("addProposals", (names, contents))
+            .map_err(BallotError::serialization)?;
+        let pending = call.send().await?;
+        TxOutcome::confirm(self.client_ref(), pending).await
+    }
+
+    async fn vote(&self, votes: EncVotes, proof: Vec<H256>) -> Result<TxOutcome, BallotError> {
+        let votes = votes.into_bytes();
+        let proof = proof.into_iter().map(H256::to_fixed_bytes).collect();
+        let call = Ballot::vote(self, votes, proof);
+        let pending = call.send().await?;
+        TxOutcome::confirm(self.client_ref(), pending).await
+    }
+
+    async fn change_vote(
+        &self,
+        votes: EncVotes,
+        proof: Vec<H256>,
+    ) -> Result<TxOutcome, BallotError> {
+        let votes = votes.into_bytes();
+        let proof = proof.into_iter().map(H256::to_fixed_bytes).collect();
+        let call = Ballot::change_vote(self, votes, proof);
+        let pending = call.send().await?;
+        TxOutcome::confirm(self.client_ref(), pending).await
+    }
+
+    async fn vote_by_sig(&self, vote: SignedVote) -> Result<TxOutcome, BallotError> {
+        let call = vote.into_call(self);
+        let pending = call.send().await?;
+        TxOutcome::confirm(self.client_ref(), pending).await
+    }
+
+    async fn open(&self, voting_ends: u64) -> Result<TxOutcome, BallotError> {
+        let call = Ballot::open(self, voting_ends.into());
+        let pending = call.send().await?;
+        TxOutcome::confirm(self.client_ref(), pending).await
+    }
+
+    async fn close(&self) -> Result<TxOutcome, BallotError> {
+        let call = Ballot::close(self);
+        let pending = call.send().await?;
+        TxOutcome::confirm(self.client_ref(), pending).await
+    }
+
+    async fn events(
+        &self,
+        from_block: Option<u64>,
+    ) -> Result<BoxStream<'_, Result<BallotEvent, BallotError>>, BallotError> {
+        Ok(events::watch(self, from_block).await?.boxed())
+    }
+}
@@ -0,0 +1,210 @@
+//! Batched ballot transactions.
+//!
+//! Proposals are batched through the ballot's own `addProposals`, which adds any number of
+//! proposals in one transaction, all or none. [`add_proposals_many`] sends one such transaction
+//! per ballot, all at once, so a ballot that rejects its proposals doesn't stop the others.
+//!
+//! Votes can't be batched through a contract. The ballot records each vote under `msg.sender`,
+//! which for a batched call would be the batching contract rather than the voter, so every
+//! batched voter would overwrite everyone else's vote. [`vote_many`] sends one transaction per
+//! ballot instead, all at once, and waits for all of them together.
+
+use std::{str::FromStr, sync::Arc};
+
+use bindings::ballot::Ballot;
+use ethers::{
+    signers::{LocalWallet, Signer},
+    types::{Address, H256},
+};
+use futures::future;
+
+use crate::{
+    client::encrypt_votes, runtime, voter_proof, Allowlist, BallotBackend, BallotError,
+    BallotMiddleware, EncVotes, KeyStore, NetworkConfig, TxOutcome, VotingRule,
+};
+
+/// What happened to one call of a batch.
+#[derive(Clone, Debug, uniffi::Record)]
+pub struct CallOutcome {
+    /// Address of the ballot called, as lower case hex without a `0x` prefix
+    pub contract_address: String,
+    /// Why the call failed; `None` if it succeeded
+    pub error: Option<String>,
+    /// The call's own transaction, if it had one and it was mined
+    pub transaction: Option<TxOutcome>,
+}
+
+impl CallOutcome {
+    pub fn new(
+        contract_address: Address,
+        error: Option<String>,
+        transaction: Option<TxOutcome>,
+    ) -> Self {
+        Self { contract_address: format!("{contract_address:x}"), error, transaction }
+    }
+
+    pub fn is_success(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+impl From<(Address, Result<TxOutcome, BallotError>)> for CallOutcome {
+    fn from((address, transaction): (Address, Result<TxOutcome, BallotError>)) -> Self {
+        match transaction {
+            Ok(transaction) if transaction.is_success() => {
+                CallOutcome::new(address, None, Some(transaction))
+            }
+            Ok(transaction) => {
+                let error = transaction.revert_reason.clone().unwrap_or("reverted".into());
+                CallOutcome::new(address, Some(error), Some(transaction))
+            }
+            Err(e) => CallOutcome::new(address, Some(e.to_string()), None),
+        }
+    }
+}
+
+/// Add `(name, contents)` proposals to each ballot, in one `addProposals` transaction per ballot.
+///
+/// All the transactions are sent at once, like [`vote_many`]'s. Returns one outcome per ballot,
+/// in order. A ballot that rejects its proposals adds none of them, and doesn't stop the others.
+pub async fn add_proposals_many(
+    client: Arc<BallotMiddleware>,
+    proposals: Vec<(Address, Vec<(String, String)>)>,
+) -> Vec<CallOutcome> {
+    let transactions = future::join_all(proposals.into_iter().map(|(address, proposals)| {
+        let ballot = Ballot::new(address, client.clone());
+        async move { (address, BallotBackend::add_proposals(&ballot, proposals).await) }
+    }))
+    .await;
+
+    transactions.into_iter().map(CallOutcome::from).collect()
+}
+
+/// Cast `votes` on each ballot, with the ballot's allowlist proof (empty if it has none).
+///
+/// All the transactions are sent at once. The client gives them consecutive nonces in order and
+/// waits for each to be confirmed, so waiting for one doesn't hold up sending the next.
+///
+/// Returns one outcome per ballot, in order. A ballot that rejects its votes doesn't stop the
+/// others.
+pub async fn vote_many(
+    client: Arc<BallotMiddleware>,
+    votes: Vec<(Address, EncVotes, Vec<H256>)>,
+) -> Result<Vec<CallOutcome>, BallotError> {
+    let calls: Vec<_> = votes
+        .into_iter()
+        .map(|(address, votes, proof)| {
+            let votes = votes.into_bytes();
+            let proof = proof.into_iter().map(H256::to_fixed_bytes).collect();
+            (address, Ballot::new(address, client.clone()).vote(votes, proof))
+        })
+        .collect();
+
+    // The middleware only lets one transaction at a time take a nonce and reach the node, in
+    // the order they're polled, then confirms them concurrently
+    let transactions = future::join_all(calls.iter().map(|(_, call)| async {
+        let pending = call.send().await?;
+        TxOutcome::confirm(&*client, pending).await
+    }))
+    .await;
+
+    Ok(calls
+        .iter()
+        .zip(transactions)
+        .map(|((address, _), transaction)| CallOutcome::from((*address, transaction)))
+        .collect())
+}
+
+/// A proposal to add as part of a batch.
+#[derive(Clone, Debug, uniffi::Record)]
+pub struct NewProposal {
+    pub contract_address: String,
+    pub name: String,
+    pub contents: String,
+}
+
+/// Votes for one ballot, as part of [`submit_votes_many`].
+#[derive(Clone, Debug, uniffi::Record)]
+pub struct BallotVotes {
+    pub contract_address: String,
+    /// One vote per proposal
+    pub votes: Vec<u64>,
+    /// The allowlist the ballot was deployed with, if it has one
+    pub allowlist: Option<Arc<Allowlist>>,
+}
+
+/// Add `proposals`, to any number of ballots, with one transaction per ballot.
+///
+/// Returns one outcome per ballot, in the order each ballot first appears in `proposals`.
+#[uniffi::export]
+pub async fn add_proposals(
+    proposals: Vec<NewProposal>,
+    wallet_key: String,
+    network: NetworkConfig,
+) -> Result<Vec<CallOutcome>, BallotError> {
+    let wallet = LocalWallet::from_str(&wallet_key).map_err(BallotError::key)?;
+    let client = network.client(wallet)?;
+
+    let mut by_ballot: Vec<(Address, Vec<(String, String)>)> = Vec::new();
+    for proposal in proposals {
+        let address =
+            Address::from_str(&proposal.contract_address).map_err(BallotError::address)?;
+        let proposal = (proposal.name, proposal.contents);
+        match by_ballot.iter_mut().find(|(ballot, _)| *ballot == address) {
+            Some((_, proposals)) => proposals.push(proposal),
+            None => by_ballot.push((address, vec![proposal])),
+        }
+    }
+    runtime::spawn(async move { Ok(add_proposals_many(client, by_ballot).await) }).await
+}
+
+/// Check, encrypt and cast votes on several ballots at once.
+///
+/// Every ballot's votes are checked against `rule` and encrypted before anything is sent, so a
+/// ballot that fails validation fails the whole call. A ballot the wallet has already voted on
+/// is skipped, with an outcome carrying a [`BallotError::AlreadyVoted`] error, unless `replace`
+/// is set, in which case the new votes replace the old ones.
+#[uniffi::export]
+pub async fn submit_votes_many(
+    ballots: Vec<BallotVotes>,
+    public_key: String,
+    private_key: String,
+    wallet_key: String,
+    network: NetworkConfig,
+    rule: VotingRule,
+    replace: bool,
+) -> Result<Vec<CallOutcome>, BallotError> {
+    network.ensure_fhe_precompiles()?;
+    let keys = KeyStore::init(public_key, private_key, wallet_key)?;
+    let client = keys.client(&network)?;
+    runtime::spawn(async move {
+        let voter = keys.wallet.address();
+        let mut votes = Vec::with_capacity(ballots.len());
+        let mut skipped = Vec::new();
+        for (index, ballot) in ballots.into_iter().enumerate() {
+            let address =
+                Address::from_str(&ballot.contract_address).map_err(BallotError::address)?;
+            let backend = Ballot::new(address, client.clone());
+            // Checked first, so a skipped ballot doesn't cost an encryption
+            if !replace && BallotBackend::has_voted(&backend, voter).await? {
+                let e = BallotError::already_voted(format!(
+                    "{voter:?} has already voted on this ballot; set replace to replace it"
+                ));
+                skipped.push((index, CallOutcome::new(address, Some(e.to_string()), None)));
+                continue;
+            }
+            let voter_root = BallotBackend::voter_root(&backend).await?;
+            let proof = voter_proof(voter_root, voter, ballot.allowlist.as_deref())?;
+            let encrypted =
+                encrypt_votes(&backend, &keys.public_key, ballot.votes, rule.clone()).await?;
+            votes.push((address, encrypted, proof));
+        }
+
+        let mut outcomes = vote_many(client, votes).await?;
+        for (index, outcome) in skipped {
+            outcomes.insert(index, outcome);
+        }
+        Ok(outcomes)
+    })
+    .await
+}
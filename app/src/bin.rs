@@ -1,31 +1,29 @@
-use std::{fs, path::PathBuf, str::FromStr, sync::Arc};
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
 
 use bindings::ballot::Ballot;
 use clap::{Parser, Subcommand, ValueEnum};
 use ethers::{
-    prelude::rand::thread_rng,
-    providers::{Http, Provider},
+    providers::Middleware,
     signers::{LocalWallet, Signer},
-    types::Address,
+    types::{Address, H256},
+    utils::{format_ether, format_units},
 };
 use eyre::{bail, Result};
-use sunscreen_web3::{
-    testnet::parasol::{generate_keys, PARASOL, RUNTIME},
-    AsBytes, AsFile, AsNum, Ciphertext, PrivateKey, PublicKey, SignedMiddleware, Unsigned256,
+use futures::StreamExt;
+use sunscreen_ballot::{
+    decrypt_tallies, encrypt_votes, fetch_network_key, network_key, relay, sign_vote,
+    unpack_tallies, verify_vote_call, voter_proof, Allowlist, BallotBackend, CallOutcome,
+    KeyBundle, KeyStore, MockChain, NetworkConfig, Packing, ParamsReport, Phase, TxOutcome,
+    VotingRule,
 };
-
-
-
-#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
-enum NetworkOption {
-    /// Locally runnying Anvil node at http://localhost:8545
-    ///
-    /// If you supply this option, you probably want to supply a --wallet-key
-    /// as well, and pass in one of the Anvil account secret keys.
-    Local,
-    /// Parasol network
-    Parasol,
-}
+use sunscreen_web3::AsBytes;
+use zeroize::Zeroizing;
 
 #[derive(Parser, Debug)]
 struct Args {
@@ -33,18 +31,74 @@ struct Args {
     #[arg(short, long, default_value = ".keys")]
     key_store: PathBuf,
 
-    /// Network to connect to
-    #[arg(short, long, value_enum, default_value_t = NetworkOption::Parasol)]
-    network: NetworkOption,
+    /// Network to connect to: `parasol`, `local` (an Anvil node at http://localhost:8545), or
+    /// any profile defined in --network-config
+    ///
+    /// If you use `local`, you probably want to supply a --wallet-key as well, and pass in one of
+    /// the Anvil account secret keys.
+    #[arg(short, long, default_value = NetworkConfig::PARASOL)]
+    network: String,
+
+    /// TOML file with additional network profiles
+    #[arg(long)]
+    network_config: Option<PathBuf>,
+
+    /// RPC endpoint (override whatever the network profile says)
+    #[arg(long)]
+    rpc_url: Option<String>,
+
+    /// Chain id (override whatever the network profile says)
+    #[arg(long)]
+    chain_id: Option<u64>,
+
+    /// Blocks a transaction needs, counting the one it's in, before a command counts it as sent
+    /// (override whatever the network profile says)
+    #[arg(long)]
+    confirmations: Option<u64>,
+
+    /// Seconds to wait for a transaction to be confirmed before giving up (override whatever the
+    /// network profile says)
+    #[arg(long)]
+    tx_timeout: Option<u64>,
 
     /// Wallet key (override whatever wallet is in the key_store)
+    ///
+    /// If the key store is passphrase protected, the passphrase is read from
+    /// `BALLOT_PASSPHRASE` or prompted for.
     #[arg(short, long)]
     wallet_key: Option<String>,
 
+    /// Run against a mock chain saved in this file instead of a network. The file is created if
+    /// it doesn't exist.
+    ///
+    /// The mock evaluates votes with the local FHE runtime, so no node or funds are needed.
+    #[arg(long)]
+    mock_chain: Option<PathBuf>,
+
     #[command(subcommand)]
     command: Commands,
 }
 
+impl Args {
+    /// Resolve the selected network profile, applying any command line overrides.
+    fn network(&self) -> Result<NetworkConfig> {
+        let mut network = NetworkConfig::resolve(&self.network, self.network_config.as_deref())?;
+        if let Some(rpc_url) = &self.rpc_url {
+            network.rpc_url = rpc_url.clone();
+        }
+        if let Some(chain_id) = self.chain_id {
+            network.chain_id = chain_id;
+        }
+        if let Some(confirmations) = self.confirmations {
+            network.transactions.confirmations = confirmations;
+        }
+        if let Some(tx_timeout) = self.tx_timeout {
+            network.transactions.timeout_secs = tx_timeout;
+        }
+        Ok(network)
+    }
+}
+
 #[derive(Debug, Subcommand)]
 enum Commands {
     /// Generate keys
@@ -52,138 +106,803 @@ enum Commands {
         /// Overwrite keys if they exist
         #[arg(short, long)]
         force: bool,
+
+        /// Encrypt the wallet key and FHE private key under a passphrase
+        #[arg(short, long)]
+        passphrase: bool,
     },
 
-    Test,
-
-    // /// Add a proposal
-    // AddProposal {
-    //     /// Address of deployed  contract
-    //     #[arg(short, long)]
-    //     contract_address: Address,
-    // },
-    //
-    // /// Vote
-    // Vote {
-    //     /// Address of deployed  contract
-    //     #[arg(short, long)]
-    //     contract_address: Address,
-    // },
-}
-struct KeyStore {
-    wallet: LocalWallet,
-    public_key: PublicKey,
-    private_key: PrivateKey,
-}
-
-impl KeyStore {
-    const WALLET_PATH: &'static str = "wallet.sk";
-    const PRIVATE_KEY_PATH: &'static str = "fhe.pri";
-    const PUBLIC_KEY_PATH: &'static str = "fhe.pub";
-
-    /// Generate new keys and save them to the specified directory.
-    fn generate(parent_dir: PathBuf, force: bool) -> Result<Self> {
-        // Throw errors if necessary
-        if !force {
-            for file in [Self::WALLET_PATH, Self::PRIVATE_KEY_PATH, Self::PUBLIC_KEY_PATH] {
-                let path = parent_dir.join(file);
-                if path.exists() {
-                    bail!("{} already exists; use --force to overwrite it", path.display());
-                }
-            }
+    /// Check the key store passphrase and show which wallet it holds
+    Unlock,
+
+    /// Change the key store passphrase, or add one to an unprotected key store
+    ChangePassphrase {
+        /// Remove the passphrase and store the keys in plaintext instead
+        #[arg(long)]
+        remove: bool,
+    },
+
+    /// Export the key store as a single bundle, e.g. to import into the app on another device
+    Export {
+        /// File to write the bundle to
+        #[arg(short, long)]
+        output: PathBuf,
+
+        /// Encrypt the bundle under a passphrase
+        #[arg(short, long)]
+        passphrase: bool,
+    },
+
+    /// Replace the key store with the keys in a bundle
+    Import {
+        /// Bundle produced by `export` or the app
+        #[arg(short, long)]
+        input: PathBuf,
+
+        /// Overwrite keys if they exist
+        #[arg(short, long)]
+        force: bool,
+
+        /// Encrypt the imported wallet key and FHE private key under a passphrase
+        #[arg(short, long)]
+        passphrase: bool,
+    },
+
+    /// Build a voter allowlist from a voter roll, and print its root to deploy with
+    Allowlist {
+        /// Voter roll: a JSON array of addresses (or of objects with an `address` field), or CSV
+        /// with the address in the first column
+        #[arg(short, long)]
+        voters: PathBuf,
+
+        /// File to write the allowlist, with every voter's proof, to
+        #[arg(short, long)]
+        output: PathBuf,
+    },
+
+    /// Deploy a new ballot contract
+    Deploy {
+        /// Only let voters on this allowlist vote
+        #[arg(long)]
+        allowlist: Option<PathBuf>,
+
+        /// Count every proposal in one packed ciphertext, so a vote costs one FHE addition however
+        /// many proposals there are
+        #[arg(long)]
+        packed: bool,
+
+        /// Most voters a packed ballot has to count (defaults to the allowlist's size)
+        #[arg(long, requires = "packed")]
+        max_voters: Option<u64>,
+
+        /// Most votes one voter can give a single proposal on a packed ballot
+        #[arg(long, requires = "packed", default_value_t = 1)]
+        max_vote: u64,
+    },
+
+    /// Add a proposal
+    AddProposal {
+        /// Address of deployed contract
+        #[arg(short, long)]
+        contract_address: Address,
+
+        /// Short name of the proposal
+        #[arg(long)]
+        name: String,
+
+        /// Full text of the proposal
+        #[arg(long, default_value = "")]
+        contents: String,
+    },
+
+    /// Add several proposals in one transaction; all of them, or none if any can't be added
+    AddProposals {
+        /// Address of deployed contract
+        #[arg(short, long)]
+        contract_address: Address,
+
+        /// Proposals to add, as `NAME` or `NAME=CONTENTS`
+        #[arg(required = true, value_parser = parse_proposal)]
+        proposals: Vec<(String, String)>,
+    },
+
+    /// List the proposals on a ballot
+    ListProposals {
+        /// Address of deployed contract
+        #[arg(short, long)]
+        contract_address: Address,
+    },
+
+    /// Stop adding proposals and open voting, until a deadline
+    Open {
+        /// Address of deployed contract
+        #[arg(short, long)]
+        contract_address: Address,
+
+        /// How long voting stays open, in seconds
+        #[arg(long, required_unless_present = "ends", conflicts_with = "ends")]
+        duration: Option<u64>,
+
+        /// When voting ends, as a Unix timestamp
+        #[arg(long)]
+        ends: Option<u64>,
+    },
+
+    /// Close voting and publish the results; before the deadline only the deployer can
+    Close {
+        /// Address of deployed contract
+        #[arg(short, long)]
+        contract_address: Address,
+    },
+
+    /// Print the final tally of every proposal, once voting has closed
+    Results {
+        /// Address of deployed contract
+        #[arg(short, long)]
+        contract_address: Address,
+    },
+
+    /// Vote, replacing any previous vote from the same wallet
+    Vote {
+        /// Address of deployed contract
+        #[arg(short, long)]
+        contract_address: Address,
+
+        /// One vote per proposal, in proposal order (e.g. `--votes 0,1,0`)
+        #[arg(long, value_delimiter = ',', required = true)]
+        votes: Vec<u64>,
+
+        /// Which ballots are valid; checked before anything is encrypted
+        #[arg(long, value_enum, default_value_t = Rule::Approval)]
+        rule: Rule,
+
+        /// Maximum total votes (`budgeted`) or credits (`quadratic`)
+        #[arg(long, required_if_eq_any = [("rule", "budgeted"), ("rule", "quadratic")])]
+        budget: Option<u64>,
+
+        /// Allowlist to prove eligibility with, if the ballot has one
+        #[arg(long)]
+        allowlist: Option<PathBuf>,
+
+        /// Sign the votes and have the `ballot-relayer` at this URL submit them, paying the gas
+        #[arg(long, conflicts_with = "mock_chain")]
+        relayer: Option<String>,
+    },
+
+    /// Vote on several ballots at once, replacing any previous votes from the same wallet
+    ///
+    /// Each ballot still gets its own transaction, but they are all sent before any is waited on.
+    VoteMany {
+        /// A ballot and its votes, as `ADDRESS=VOTES` (e.g. `--ballot 0x5FbD...0aa3=0,1,0`);
+        /// repeat for each ballot
+        #[arg(long = "ballot", required = true, value_parser = parse_ballot_votes)]
+        ballots: Vec<(Address, Vec<u64>)>,
+
+        /// Which ballots are valid; every ballot is checked before anything is sent
+        #[arg(long, value_enum, default_value_t = Rule::Approval)]
+        rule: Rule,
+
+        /// Maximum total votes (`budgeted`) or credits (`quadratic`)
+        #[arg(long, required_if_eq_any = [("rule", "budgeted"), ("rule", "quadratic")])]
+        budget: Option<u64>,
+
+        /// Allowlists to prove eligibility with; each ballot uses the one it was deployed with
+        #[arg(long)]
+        allowlist: Vec<PathBuf>,
+    },
+
+    /// Check that the local keys, the contract's network key and this build agree on FHE
+    /// parameters
+    VerifyParams {
+        /// Address of deployed contract
+        #[arg(short, long)]
+        contract_address: Address,
+    },
+
+    /// Predict the gas and cost of running a ballot, before or after deploying it
+    Estimate {
+        /// Deployed contract to also ask `eth_estimateGas` about
+        #[arg(short, long)]
+        contract_address: Option<Address>,
+
+        /// Number of proposals (defaults to the deployed contract's)
+        #[arg(short, long, required_unless_present = "contract_address")]
+        proposals: Option<u64>,
+
+        /// Name of the sample proposal to estimate `add_proposal` with
+        #[arg(long, default_value = "Proposal")]
+        name: String,
+
+        /// Full text of the sample proposal
+        #[arg(long, default_value = "")]
+        contents: String,
+    },
+
+    /// Print proposals and votes as they happen
+    Watch {
+        /// Address of deployed contract
+        #[arg(short, long)]
+        contract_address: Address,
+
+        /// Also print everything since this block
+        #[arg(long)]
+        from_block: Option<u64>,
+    },
+
+    /// Decrypt the current tally of every proposal
+    Tally {
+        /// Address of deployed contract
+        #[arg(short, long)]
+        contract_address: Address,
+    },
+
+    /// Check that a vote transaction carries the ciphertexts its ballot counts
+    AuditVote {
+        /// Hash of the `vote`, `changeVote` or `voteBySig` transaction
+        transaction: H256,
+    },
+}
+
+/// Command line names for [`VotingRule`]s.
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum Rule {
+    /// Exactly one proposal gets a 1
+    SingleChoice,
+    /// Each proposal gets a 0 or 1
+    Approval,
+    /// Any votes per proposal, up to --budget in total
+    Budgeted,
+    /// n votes for a proposal cost n² credits, up to --budget in total
+    Quadratic,
+}
+
+impl Rule {
+    fn voting_rule(self, budget: Option<u64>) -> VotingRule {
+        // clap guarantees a budget for the rules that need one
+        let budget = budget.unwrap_or_default();
+        match self {
+            Self::SingleChoice => VotingRule::SingleChoice,
+            Self::Approval => VotingRule::Approval,
+            Self::Budgeted => VotingRule::Budgeted { max_total: budget },
+            Self::Quadratic => VotingRule::Quadratic { credits: budget },
         }
+    }
+}
 
-        // Generate new keys
-        let (public_key, private_key) = generate_keys()?;
-        let wallet = LocalWallet::new(&mut thread_rng());
+/// Parse a proposal given as `NAME` or `NAME=CONTENTS`.
+fn parse_proposal(arg: &str) -> Result<(String, String), String> {
+    let (name, contents) = arg.split_once('=').unwrap_or((arg, ""));
+    if name.is_empty() {
+        return Err("proposal name must not be empty".into());
+    }
+    Ok((name.into(), contents.into()))
+}
 
-        // Write keys to files
-        public_key.write(parent_dir.join(Self::PUBLIC_KEY_PATH))?;
-        private_key.write(parent_dir.join(Self::PRIVATE_KEY_PATH))?;
-        wallet.write(parent_dir.join(Self::WALLET_PATH))?;
+/// Parse a ballot's votes given as `ADDRESS=VOTES`, with the votes separated by commas.
+fn parse_ballot_votes(arg: &str) -> Result<(Address, Vec<u64>), String> {
+    let (address, votes) =
+        arg.split_once('=').ok_or("expected ADDRESS=VOTES, e.g. 0x5FbD...0aa3=0,1,0")?;
+    let address = Address::from_str(address).map_err(|e| format!("invalid address: {e}"))?;
+    let votes = votes
+        .split(',')
+        .map(|vote| vote.trim().parse().map_err(|e| format!("invalid vote {vote:?}: {e}")))
+        .collect::<Result<_, _>>()?;
+    Ok((address, votes))
+}
 
-        // Log messages to the user
-        eprintln!("Saved new keys under directory {}", parent_dir.display());
-        eprintln!(
-            "Head to {}?address={:?} for some free SPETH!",
-            PARASOL.faucet_url,
-            wallet.address()
-        );
+/// Read a passphrase from `env_key`, or prompt for one if it isn't set.
+fn read_passphrase(env_key: &str, prompt: &str, confirm: bool) -> Result<Zeroizing<String>> {
+    if let Ok(passphrase) = env::var(env_key) {
+        return Ok(Zeroizing::new(passphrase));
+    }
 
-        Ok(Self { wallet, public_key, private_key })
+    let passphrase = Zeroizing::new(rpassword::prompt_password(prompt)?);
+    if passphrase.is_empty() {
+        bail!("passphrase must not be empty");
+    }
+    if confirm {
+        let again = Zeroizing::new(rpassword::prompt_password("Confirm passphrase: ")?);
+        if *again != *passphrase {
+            bail!("passphrases do not match");
+        }
     }
+    Ok(passphrase)
+}
 
-    fn init(parent_dir: PathBuf, wallet_key: Option<String>) -> Result<Self> {
-        let public_key = PublicKey::read(parent_dir.join(Self::PUBLIC_KEY_PATH))?;
-        let private_key = PrivateKey::read(parent_dir.join(Self::PRIVATE_KEY_PATH))?;
-        let wallet = match wallet_key {
-            Some(s) => LocalWallet::from_str(&s)?,
-            None => LocalWallet::read(parent_dir.join(Self::WALLET_PATH))?,
-        };
-        Ok(Self { wallet, public_key, private_key })
+/// Load the key store, unlocking it if necessary.
+fn load_keys(dir: &Path, wallet_key: Option<&str>) -> Result<KeyStore> {
+    let passphrase = if KeyStore::is_sealed(dir) {
+        Some(read_passphrase("BALLOT_PASSPHRASE", "Key store passphrase: ", false)?)
+    } else {
+        None
+    };
+    let mut keys = KeyStore::read(dir, passphrase.as_deref().map(String::as_str))?;
+    if let Some(wallet_key) = wallet_key {
+        keys.wallet = LocalWallet::from_str(wallet_key)?;
     }
+    Ok(keys)
+}
 
-    fn client(&self, network: NetworkOption) -> Result<Arc<SignedMiddleware>> {
-        Ok(match network {
-            NetworkOption::Local => {
-                let provider =
-                    Arc::new(Provider::<Http>::try_from("http://localhost:8545").unwrap());
-                Arc::new(SignedMiddleware::new(
-                    provider,
-                    self.wallet.clone().with_chain_id(31337_u64),
-                ))
-            }
-            NetworkOption::Parasol => PARASOL.client(self.wallet.clone()),
-        })
+/// Load the mock chain at `path`, or start a new one if there is nothing there yet.
+fn load_mock_chain(path: &Path) -> Result<Arc<MockChain>> {
+    if path.exists() {
+        Ok(MockChain::from_bytes(fs::read(path)?)?)
+    } else {
+        Ok(MockChain::new()?)
     }
+}
 
-    fn contract(
-        &self,
-        network: NetworkOption,
-        contract_address: Address,
-    ) -> Result<Ballot<SignedMiddleware>> {
-        let client = self.client(network)?;
-        let contract = Ballot::new(contract_address, client);
-        Ok(contract)
+/// The ballot at `contract_address`, on the mock chain if there is one.
+fn contract(
+    keys: &KeyStore,
+    network: &NetworkConfig,
+    mock_chain: Option<&Arc<MockChain>>,
+    contract_address: Address,
+) -> Result<Box<dyn BallotBackend>> {
+    match mock_chain {
+        Some(chain) => Ok(Box::new(chain.ballot(contract_address, keys.wallet.address()))),
+        None => {
+            network.ensure_fhe_precompiles()?;
+            Ok(Box::new(Ballot::new(contract_address, keys.client(network)?)))
+        }
     }
 }
 
+/// Read an allowlist written by the `allowlist` command.
+fn load_allowlist(path: &Path) -> Result<Arc<Allowlist>> {
+    Ok(Allowlist::from_json(fs::read_to_string(path)?)?)
+}
+
+/// The proof of eligibility the ballot needs from this wallet, empty if it has no allowlist.
+async fn proof(
+    keys: &KeyStore,
+    ballot: &dyn BallotBackend,
+    allowlists: &[Arc<Allowlist>],
+) -> Result<Vec<H256>> {
+    let voter_root = ballot.voter_root().await?;
+    let allowlists = allowlists.iter().map(|allowlist| &**allowlist);
+    Ok(voter_proof(voter_root, keys.wallet.address(), allowlists)?)
+}
+
+/// Warn that voting on the ballot at `contract_address` replaces the wallet's earlier vote.
+fn warn_replacing(keys: &KeyStore, contract_address: Address) {
+    eprintln!(
+        "Warning: {:?} has already voted on {contract_address:?}; replacing the previous vote",
+        keys.wallet.address()
+    );
+}
+
+/// Print what a mined transaction did, failing if it reverted.
+fn report_tx(outcome: &TxOutcome, network: &NetworkConfig) -> Result<()> {
+    println!("Transaction {} in block {}", outcome.tx_hash, outcome.block_number);
+    if let Some(url) = network.explorer_tx_url(&outcome.tx_hash) {
+        println!("Explorer: {url}");
+    }
+    print!("Gas used: {}", outcome.gas_used);
+    match (outcome.effective_gas_price, outcome.fee()) {
+        (Some(price), Some(fee)) => println!(
+            " at {} gwei, costing {} SPETH",
+            format_units(price, "gwei")?,
+            format_ether(fee)
+        ),
+        _ => println!(),
+    }
+    println!("Status: {}", outcome.status);
+    if !outcome.is_success() {
+        match &outcome.revert_reason {
+            Some(reason) => bail!("the transaction reverted: {reason}"),
+            None => bail!("the transaction reverted"),
+        }
+    }
+    Ok(())
+}
+
+/// Print the outcome of each call in a batch, returning how many failed.
+fn report_outcomes(outcomes: &[CallOutcome]) -> usize {
+    for outcome in outcomes {
+        let address = &outcome.contract_address;
+        let tx =
+            outcome.transaction.as_ref().map_or(String::new(), |tx| format!(" ({})", tx.tx_hash));
+        match &outcome.error {
+            None => println!("0x{address}: ok{tx}"),
+            Some(error) => println!("0x{address}: failed{tx}: {error}"),
+        }
+    }
+    outcomes.iter().filter(|outcome| !outcome.is_success()).count()
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     let args = Args::parse();
+    let network = args.network()?;
+    let mock_chain = args.mock_chain.as_deref().map(load_mock_chain).transpose()?;
     fs::create_dir_all(args.key_store.clone())?;
 
+    // Batches report failed calls only after any mock chain changes are saved
+    let mut failed_calls = 0;
     match args.command {
-        Commands::Gen { force } => {
-            KeyStore::generate(args.key_store, force)?;
-        }
-        Commands::Test => {
-            let keys = KeyStore::init(args.key_store, args.wallet_key)?;
-            let client = keys.client(NetworkOption::Parasol)?;
-           // let contract_address= 15923335699405106885;
-            let contract_address = Ballot::deploy(Arc::clone(&client), ())?.send().await?.address();
-            let x = contract_address.to_string();
-            let contract = keys.contract(args.network, contract_address)?;
-            let from = Address::from_str(&x);
-            let result = contract.get_public_key().call().await?;//.send().await.unwrap().await.unwrap().unwrap();
-            println!("{:?}", result);
-            return Ok(())
-        }
-        // Commands::Increment { contract_address } => {
-        //     let keys = KeyStore::init(args.key_store, args.wallet_key)?;
-        //     let counter = keys.contract(args.network, contract_address)?;
-        //     counter.increment().send().await?.await?;
-        // }
-        // Commands::Decrypt { contract_address } => {
-        //     let keys = KeyStore::init(args.key_store, args.wallet_key)?;
-        //     let counter = keys.contract(args.network, contract_address)?;
-        //     let value_enc = counter.reencrypt_number(keys.public_key.as_bytes()?).call().await?;
-        //     let value: Unsigned256 =
-        //         RUNTIME.decrypt(&Ciphertext::from_bytes(&value_enc)?, &keys.private_key)?;
-        //     eprintln!("Current counter value: {}", value.to())
-        // }
+        Commands::Gen { force, passphrase } => {
+            if !force && KeyStore::exists(&args.key_store) {
+                bail!(
+                    "keys already exist under {}; use --force to overwrite them",
+                    args.key_store.display()
+                );
+            }
+
+            let passphrase = if passphrase {
+                Some(read_passphrase("BALLOT_PASSPHRASE", "New passphrase: ", true)?)
+            } else {
+                None
+            };
+            let keys = KeyStore::generate()?;
+            keys.write(&args.key_store, passphrase.as_deref().map(String::as_str))?;
+
+            // Log messages to the user
+            eprintln!("Saved new keys under directory {}", args.key_store.display());
+            if let Some(faucet_url) = &network.faucet_url {
+                eprintln!(
+                    "Head to {}?address={:?} for some free SPETH!",
+                    faucet_url,
+                    keys.wallet.address()
+                );
+            }
+        }
+        Commands::Unlock => {
+            let keys = load_keys(&args.key_store, None)?;
+            let state = if KeyStore::is_sealed(&args.key_store) {
+                "unlocked"
+            } else {
+                "is not passphrase protected"
+            };
+            eprintln!("Key store {} {state}", args.key_store.display());
+            println!("{:?}", keys.wallet.address());
+        }
+        Commands::ChangePassphrase { remove } => {
+            let keys = load_keys(&args.key_store, None)?;
+            if remove {
+                keys.write(&args.key_store, None)?;
+                eprintln!("Removed passphrase; keys are now stored in plaintext");
+            } else {
+                let passphrase =
+                    read_passphrase("BALLOT_NEW_PASSPHRASE", "New passphrase: ", true)?;
+                keys.write(&args.key_store, Some(&passphrase))?;
+                eprintln!("Passphrase changed");
+            }
+        }
+        Commands::Export { output, passphrase } => {
+            let keys = load_keys(&args.key_store, None)?;
+            let passphrase = if passphrase {
+                Some(read_passphrase("BALLOT_BUNDLE_PASSPHRASE", "Bundle passphrase: ", true)?)
+            } else {
+                None
+            };
+            let bundle = KeyBundle::new(&keys);
+            fs::write(&output, bundle.to_bytes(passphrase.as_deref().map(String::as_str))?)?;
+            eprintln!(
+                "Exported wallet {:?} (params {}) to {}",
+                keys.wallet.address(),
+                bundle.params_fingerprint,
+                output.display()
+            );
+        }
+        Commands::Import { input, force, passphrase } => {
+            if !force && KeyStore::exists(&args.key_store) {
+                bail!(
+                    "keys already exist under {}; use --force to overwrite them",
+                    args.key_store.display()
+                );
+            }
+
+            let contents = fs::read(&input)?;
+            let bundle_passphrase = if KeyBundle::is_sealed(&contents) {
+                Some(read_passphrase("BALLOT_BUNDLE_PASSPHRASE", "Bundle passphrase: ", false)?)
+            } else {
+                None
+            };
+            let bundle =
+                KeyBundle::from_bytes(&contents, bundle_passphrase.as_deref().map(String::as_str))?;
+            let keys = bundle.into_key_store()?;
+
+            let passphrase = if passphrase {
+                Some(read_passphrase("BALLOT_PASSPHRASE", "New passphrase: ", true)?)
+            } else {
+                None
+            };
+            keys.write(&args.key_store, passphrase.as_deref().map(String::as_str))?;
+            eprintln!(
+                "Imported wallet {:?} into {}",
+                keys.wallet.address(),
+                args.key_store.display()
+            );
+        }
+        Commands::Allowlist { voters, output } => {
+            let allowlist = Allowlist::from_voter_roll(fs::read_to_string(&voters)?)?;
+            fs::write(&output, allowlist.to_json()?)?;
+            eprintln!(
+                "Wrote proofs for {} voters to {}",
+                allowlist.voters().len(),
+                output.display()
+            );
+            println!("{}", allowlist.root());
+        }
+        Commands::Deploy { allowlist, packed, max_voters, max_vote } => {
+            let keys = load_keys(&args.key_store, args.wallet_key.as_deref())?;
+            let allowlist = allowlist.as_deref().map(load_allowlist).transpose()?;
+            let voter_root =
+                allowlist.as_ref().map_or(H256::zero(), |allowlist| allowlist.root_hash());
+            let packing = match (packed, max_voters, &allowlist) {
+                (false, ..) => None,
+                (true, Some(max_voters), _) => Some(Packing { max_voters, max_vote }),
+                (true, None, Some(allowlist)) => {
+                    Some(Packing { max_voters: allowlist.voters().len() as u64, max_vote })
+                }
+                (true, None, None) => bail!("--max-voters is required without --allowlist"),
+            };
+            if let Some(packing) = &packing {
+                eprintln!(
+                    "Packing up to {} proposals into {} bit slots",
+                    packing.max_proposals()?,
+                    packing.slot_bits()?
+                );
+            }
+
+            let outcome = match &mock_chain {
+                Some(chain) => {
+                    let organiser = format!("{:?}", keys.wallet.address());
+                    chain.deploy(organiser, Some(format!("{voter_root:?}")), packing)?
+                }
+                None => {
+                    let client = keys.client(&network)?;
+                    network.probe_fhe_precompiles(client.clone()).await?;
+                    let slot_bits = packing.map(|packing| packing.slot_bits()).transpose()?;
+                    let args = (voter_root.0, slot_bits.unwrap_or_default());
+                    let deployer = Ballot::deploy(client.clone(), args)?;
+                    let (_, receipt) = deployer.send_with_receipt().await?;
+                    TxOutcome::from_receipt(&*client, receipt).await?
+                }
+            };
+            report_tx(&outcome, &network)?;
+            if let Some(address) = &outcome.contract_address {
+                eprintln!("Contract deployed at address 0x{address}");
+            }
+        }
+        Commands::AddProposal { contract_address, name, contents } => {
+            let keys = load_keys(&args.key_store, args.wallet_key.as_deref())?;
+            let ballot = contract(&keys, &network, mock_chain.as_ref(), contract_address)?;
+            report_tx(&ballot.add_proposal(name, contents).await?, &network)?;
+        }
+        Commands::AddProposals { contract_address, proposals } => {
+            let keys = load_keys(&args.key_store, args.wallet_key.as_deref())?;
+            let ballot = contract(&keys, &network, mock_chain.as_ref(), contract_address)?;
+            report_tx(&ballot.add_proposals(proposals).await?, &network)?;
+        }
+        Commands::ListProposals { contract_address } => {
+            let keys = load_keys(&args.key_store, args.wallet_key.as_deref())?;
+            let ballot = contract(&keys, &network, mock_chain.as_ref(), contract_address)?;
+            let proposals = ballot.get_proposals().await?;
+            for (i, proposal) in proposals.iter().enumerate() {
+                println!("{i}: {}", proposal.name);
+                if !proposal.contents.is_empty() {
+                    println!("   {}", proposal.contents);
+                }
+            }
+        }
+        Commands::Open { contract_address, duration, ends } => {
+            let keys = load_keys(&args.key_store, args.wallet_key.as_deref())?;
+            let ballot = contract(&keys, &network, mock_chain.as_ref(), contract_address)?;
+            Phase::Setup.ensure(ballot.phase().await?, "open voting")?;
+
+            // clap guarantees exactly one of the two
+            let ends = match (ends, duration) {
+                (Some(ends), _) => ends,
+                (None, duration) => {
+                    SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs()
+                        + duration.unwrap_or_default()
+                }
+            };
+            report_tx(&ballot.open(ends).await?, &network)?;
+            eprintln!("Voting is open until {ends}");
+        }
+        Commands::Close { contract_address } => {
+            let keys = load_keys(&args.key_store, args.wallet_key.as_deref())?;
+            let ballot = contract(&keys, &network, mock_chain.as_ref(), contract_address)?;
+            Phase::Voting.ensure(ballot.phase().await?, "close voting")?;
+            report_tx(&ballot.close().await?, &network)?;
+            eprintln!("Voting is closed");
+        }
+        Commands::Results { contract_address } => {
+            let keys = load_keys(&args.key_store, args.wallet_key.as_deref())?;
+            let ballot = contract(&keys, &network, mock_chain.as_ref(), contract_address)?;
+            Phase::Closed.ensure(ballot.phase().await?, "read the results")?;
+            let proposals = ballot.get_proposals().await?;
+            let results = ballot.get_results().await?;
+            for (proposal, result) in proposals.iter().zip(results) {
+                println!("{}: {result}", proposal.name);
+            }
+        }
+        Commands::Vote { contract_address, votes, rule, budget, allowlist, relayer } => {
+            let keys = load_keys(&args.key_store, args.wallet_key.as_deref())?;
+            let ballot = contract(&keys, &network, mock_chain.as_ref(), contract_address)?;
+            let allowlists: Vec<_> =
+                allowlist.as_deref().map(load_allowlist).transpose()?.into_iter().collect();
+            // Checked before the slow encryption, so the warning comes straight away
+            let voted = ballot.has_voted(keys.wallet.address()).await?;
+            if voted {
+                warn_replacing(&keys, contract_address);
+            }
+            let proof = proof(&keys, &*ballot, &allowlists).await?;
+            let rule = rule.voting_rule(budget);
+            let encrypted = encrypt_votes(&*ballot, &keys.public_key, votes, rule).await?;
+
+            let outcome = match relayer {
+                Some(relayer) => {
+                    let vote = sign_vote(&keys.wallet, &*ballot, encrypted, proof).await?;
+                    relay(&relayer, &vote).await?
+                }
+                None if voted => ballot.change_vote(encrypted, proof).await?,
+                None => ballot.vote(encrypted, proof).await?,
+            };
+            report_tx(&outcome, &network)?;
+        }
+        Commands::VoteMany { ballots, rule, budget, allowlist } => {
+            let keys = load_keys(&args.key_store, args.wallet_key.as_deref())?;
+            let rule = rule.voting_rule(budget);
+            let allowlists =
+                allowlist.iter().map(|path| load_allowlist(path)).collect::<Result<Vec<_>>>()?;
+
+            // Encrypt everything first, so a ballot that fails its checks stops the whole batch
+            let mut encrypted = Vec::with_capacity(ballots.len());
+            for (address, votes) in ballots {
+                let ballot = contract(&keys, &network, mock_chain.as_ref(), address)?;
+                if ballot.has_voted(keys.wallet.address()).await? {
+                    warn_replacing(&keys, address);
+                }
+                let proof = proof(&keys, &*ballot, &allowlists).await?;
+                let votes = encrypt_votes(&*ballot, &keys.public_key, votes, rule.clone()).await?;
+                encrypted.push((address, votes, proof));
+            }
+
+            let outcomes = match &mock_chain {
+                Some(_) => {
+                    let mut outcomes = Vec::with_capacity(encrypted.len());
+                    for (address, votes, proof) in encrypted {
+                        let ballot = contract(&keys, &network, mock_chain.as_ref(), address)?;
+                        outcomes.push(match ballot.vote(votes, proof).await {
+                            Ok(tx) => CallOutcome::new(address, None, Some(tx)),
+                            Err(e) => CallOutcome::new(address, Some(e.to_string()), None),
+                        });
+                    }
+                    outcomes
+                }
+                None => sunscreen_ballot::vote_many(keys.client(&network)?, encrypted).await?,
+            };
+            failed_calls = report_outcomes(&outcomes);
+        }
+        Commands::VerifyParams { contract_address } => {
+            let keys = load_keys(&args.key_store, args.wallet_key.as_deref())?;
+            let ballot = contract(&keys, &network, mock_chain.as_ref(), contract_address)?;
+            let network_key = fetch_network_key(&*ballot).await?;
+            let report = ParamsReport::new(&keys.public_key, &network_key);
+            println!("runtime:     {}", report.runtime);
+            println!("local key:   {}", report.local_key);
+            println!("network key: {}", report.network_key);
+            if !report.is_compatible() {
+                for difference in &report.differences {
+                    eprintln!("  {difference}");
+                }
+                bail!("FHE parameters do not match");
+            }
+            eprintln!("FHE parameters match");
+        }
+        Commands::Estimate { contract_address, proposals, name, contents } => {
+            if mock_chain.is_some() {
+                bail!("the mock chain doesn't charge gas; estimate against a network instead");
+            }
+            let keys = load_keys(&args.key_store, args.wallet_key.as_deref())?;
+            let client = keys.client(&network)?;
+            let ballot = contract_address.map(|address| Ballot::new(address, client.clone()));
+            let proposals = match (proposals, &ballot) {
+                (Some(proposals), _) => proposals,
+                (None, Some(ballot)) => ballot.get_proposals().call().await?.len() as u64,
+                (None, None) => bail!("--proposals is required without --contract-address"),
+            };
+            let estimate =
+                sunscreen_ballot::estimate(client, ballot.as_ref(), proposals, &name, &contents)
+                    .await?;
+
+            let fhe = &estimate.fhe_gas;
+            println!(
+                "{} proposals, {} byte ciphertexts, gas price {} gwei",
+                estimate.proposal_count,
+                estimate.ciphertext_size,
+                format_units(estimate.gas_price, "gwei")?
+            );
+            println!(
+                "FHE gas: add {}, subtract {}, encrypt {}, reencrypt {}, network key {}",
+                fhe.add, fhe.subtract, fhe.encrypt, fhe.reencrypt, fhe.network_public_key
+            );
+            for (layout, estimates) in [
+                ("One count per proposal", &estimate.estimates),
+                ("Packed into one count", &estimate.packed_estimates),
+            ] {
+                println!();
+                println!("{layout}:");
+                println!(
+                    "{:<20} {:>12} {:>12} {:>12} {:>16} {:>14}",
+                    "function",
+                    "FHE gas",
+                    "EVM gas",
+                    "predicted",
+                    "eth_estimateGas",
+                    "cost (SPETH)"
+                );
+                for gas in estimates {
+                    let node_gas = gas.node_gas.map(|gas| gas.to_string()).unwrap_or("-".into());
+                    println!(
+                        "{:<20} {:>12} {:>12} {:>12} {:>16} {:>14}",
+                        gas.function,
+                        gas.fhe_gas,
+                        gas.evm_gas,
+                        gas.total_gas(),
+                        node_gas,
+                        format_ether(estimate.cost(gas))
+                    );
+                }
+            }
+        }
+        Commands::Watch { contract_address, from_block } => {
+            let keys = load_keys(&args.key_store, args.wallet_key.as_deref())?;
+            let ballot = contract(&keys, &network, mock_chain.as_ref(), contract_address)?;
+            let mut events = ballot.events(from_block).await?;
+            while let Some(event) = events.next().await {
+                println!("{}", event?);
+            }
+        }
+        Commands::Tally { contract_address } => {
+            let keys = load_keys(&args.key_store, args.wallet_key.as_deref())?;
+            let ballot = contract(&keys, &network, mock_chain.as_ref(), contract_address)?;
+            network_key(&*ballot, &keys.public_key).await?;
+            let proposals = ballot.get_proposals().await?;
+            let tallys = ballot.get_proposal_tallys(keys.public_key.as_bytes()?).await?;
+            let tallys = decrypt_tallies(tallys, &keys.private_key).await?;
+            let tallys = unpack_tallies(tallys, ballot.slot_bits().await?, proposals.len())?;
+            for (proposal, tally) in proposals.iter().zip(tallys) {
+                println!("{}: {tally}", proposal.name);
+            }
+        }
+        Commands::AuditVote { transaction } => {
+            if mock_chain.is_some() {
+                bail!("the mock chain keeps no transactions to audit");
+            }
+            let keys = load_keys(&args.key_store, args.wallet_key.as_deref())?;
+            let Some(tx) = keys.client(&network)?.get_transaction(transaction).await? else {
+                bail!("transaction {transaction:?} not found");
+            };
+            let Some(contract_address) = tx.to else {
+                bail!("transaction {transaction:?} deploys a contract rather than voting");
+            };
+            let ballot = contract(&keys, &network, None, contract_address)?;
+            let proposals = ballot.get_proposals().await?;
+            let audit = verify_vote_call(&tx.input, proposals.len(), ballot.slot_bits().await?)?;
+
+            println!("{} on {contract_address:?}", audit.function);
+            if let Some(voter) = &audit.voter {
+                println!("Signed by: 0x{voter}");
+            }
+            if audit.is_well_formed() {
+                println!("Well-formed; this says nothing about the votes encrypted");
+            }
+            for problem in &audit.problems {
+                println!("Malformed: {problem}");
+            }
+        }
     }
 
+    if let (Some(path), Some(chain)) = (&args.mock_chain, &mock_chain) {
+        fs::write(path, chain.to_bytes()?)?;
+    }
+    if failed_calls > 0 {
+        bail!("{failed_calls} calls failed");
+    }
     Ok(())
 }
-
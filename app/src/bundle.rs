@@ -0,0 +1,159 @@
+//! Portable key bundles.
+//!
+//! A [`KeyBundle`] holds everything that makes up a voter's identity, so it can be moved between
+//! the CLI and the app in one piece. Bundles are JSON, optionally sealed under a passphrase with
+//! the same format as the encrypted key store.
+
+use std::{
+    str::FromStr,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use ethers::{
+    signers::{LocalWallet, Signer},
+    utils::hex,
+};
+use serde::{Deserialize, Serialize};
+use sunscreen::{PrivateKey, PublicKey};
+use zeroize::Zeroizing;
+
+use crate::{params, vault, BallotError, KeyStore};
+
+/// A wallet and FHE keypair, plus the metadata needed to check them on import.
+#[derive(Serialize, Deserialize)]
+pub struct KeyBundle {
+    /// Bundle format version
+    pub version: u32,
+    /// Seconds since the unix epoch at which the bundle was exported
+    pub created_at: u64,
+    /// Fingerprint of the FHE parameters the keys were generated under
+    pub params_fingerprint: String,
+    /// Hex encoded wallet secret key
+    pub wallet_key: String,
+    pub public_key: PublicKey,
+    pub private_key: PrivateKey,
+}
+
+impl KeyBundle {
+    pub const VERSION: u32 = 1;
+
+    /// Bundle up the keys in `keys`.
+    pub fn new(keys: &KeyStore) -> Self {
+        let created_at =
+            SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or_default();
+        Self {
+            version: Self::VERSION,
+            created_at,
+            params_fingerprint: params_fingerprint(&keys.public_key),
+            wallet_key: hex::encode(keys.wallet.signer().to_bytes()),
+            public_key: keys.public_key.clone(),
+            private_key: keys.private_key.clone(),
+        }
+    }
+
+    /// Whether `bytes` is a passphrase protected bundle.
+    pub fn is_sealed(bytes: &[u8]) -> bool {
+        vault::is_sealed(bytes)
+    }
+
+    /// Serialize the bundle, sealing it under `passphrase` if one is given.
+    pub fn to_bytes(&self, passphrase: Option<&str>) -> Result<Vec<u8>, BallotError> {
+        let json = Zeroizing::new(serde_json::to_vec(self).map_err(BallotError::serialization)?);
+        match passphrase {
+            Some(passphrase) => vault::seal(&json, passphrase),
+            None => Ok(json.to_vec()),
+        }
+    }
+
+    /// Parse and check a bundle produced by [`KeyBundle::to_bytes`].
+    ///
+    /// `passphrase` is required if the bundle is sealed.
+    pub fn from_bytes(bytes: &[u8], passphrase: Option<&str>) -> Result<Self, BallotError> {
+        let bundle: Self = if Self::is_sealed(bytes) {
+            let passphrase = passphrase.ok_or_else(|| {
+                BallotError::passphrase(
+                    "key bundle is passphrase protected; a passphrase is required",
+                )
+            })?;
+            let json = vault::open(bytes, passphrase)?;
+            serde_json::from_slice(&json).map_err(BallotError::serialization)?
+        } else {
+            serde_json::from_slice(bytes).map_err(BallotError::serialization)?
+        };
+
+        if bundle.version != Self::VERSION {
+            return Err(BallotError::serialization(format!(
+                "unsupported key bundle version {}; this build reads version {}",
+                bundle.version,
+                Self::VERSION
+            )));
+        }
+        let fingerprint = params_fingerprint(&bundle.public_key);
+        if bundle.params_fingerprint != fingerprint {
+            return Err(BallotError::key(format!(
+                "key bundle fingerprint {} does not match its public key ({fingerprint})",
+                bundle.params_fingerprint
+            )));
+        }
+        Ok(bundle)
+    }
+
+    /// Unpack the bundle into a key store.
+    pub fn into_key_store(self) -> Result<KeyStore, BallotError> {
+        let wallet = LocalWallet::from_str(&self.wallet_key).map_err(BallotError::key)?;
+        Ok(KeyStore { wallet, public_key: self.public_key, private_key: self.private_key })
+    }
+}
+
+/// Short, stable identifier of the FHE parameters `public_key` was generated under.
+pub fn params_fingerprint(public_key: &PublicKey) -> String {
+    params::fingerprint(&public_key.public_key.params)
+}
+
+/// The contents of an imported [`KeyBundle`], in the shape the other exports expect.
+#[derive(uniffi::Record)]
+pub struct ImportedKeys {
+    /// JSON encoded FHE public key
+    pub public_key: String,
+    /// JSON encoded FHE private key
+    pub private_key: String,
+    /// Hex encoded wallet secret key
+    pub wallet_key: String,
+    /// Checksummed wallet address
+    pub wallet_address: String,
+    pub created_at: u64,
+    pub params_fingerprint: String,
+}
+
+/// Bundle a wallet key and FHE keypair for moving to another device, optionally sealing it under
+/// `passphrase`.
+#[uniffi::export]
+pub fn export_key_bundle(
+    public_key: String,
+    private_key: String,
+    wallet_key: String,
+    passphrase: Option<String>,
+) -> Result<Vec<u8>, BallotError> {
+    let keys = KeyStore::init(public_key, private_key, wallet_key)?;
+    KeyBundle::new(&keys).to_bytes(passphrase.as_deref())
+}
+
+/// Unpack a bundle produced by [`export_key_bundle`] or `ballot-bin export`.
+#[uniffi::export]
+pub fn import_key_bundle(
+    bundle: Vec<u8>,
+    passphrase: Option<String>,
+) -> Result<ImportedKeys, BallotError> {
+    let bundle = KeyBundle::from_bytes(&bundle, passphrase.as_deref())?;
+    let wallet = LocalWallet::from_str(&bundle.wallet_key).map_err(BallotError::key)?;
+    Ok(ImportedKeys {
+        public_key: serde_json::to_string(&bundle.public_key)
+            .map_err(BallotError::serialization)?,
+        private_key: serde_json::to_string(&bundle.private_key)
+            .map_err(BallotError::serialization)?,
+        wallet_key: bundle.wallet_key,
+        wallet_address: ethers::utils::to_checksum(&wallet.address(), None),
+        created_at: bundle.created_at,
+        params_fingerprint: bundle.params_fingerprint,
+    })
+}
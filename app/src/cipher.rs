@@ -0,0 +1,125 @@
+//! Typed ciphertexts.
+//!
+//! The contract passes ciphertexts around as plain `bytes`, which says nothing about what they
+//! decrypt to: votes are encrypted as `Unsigned64`, or packed into one `Unsigned256`, and
+//! tallies come back re-encrypted as `Unsigned256`. Each wrapper here pins a ciphertext to its
+//! plaintext type, so `encrypt` only accepts, and `decrypt` only produces, the type the contract
+//! expects at that spot. Decrypting a tally as a vote is a compile error rather than a wrong
+//! answer.
+
+use ethers::types::Bytes;
+use sunscreen::{
+    types::bfv::{Fractional, Signed, Unsigned256, Unsigned64},
+    Ciphertext, PrivateKey, PublicKey,
+};
+use sunscreen_web3::{testnet::parasol::RUNTIME, AsBytes};
+
+use crate::{packing, BallotError};
+
+macro_rules! encrypted {
+    ($(#[$doc:meta])* $name:ident => $plaintext:ty) => {
+        $(#[$doc])*
+        #[derive(Clone, Debug, PartialEq, Eq)]
+        pub struct $name(Bytes);
+
+        impl $name {
+            /// Encrypt `value` under `public_key`.
+            pub fn encrypt(
+                value: impl Into<$plaintext>,
+                public_key: &PublicKey,
+            ) -> Result<Self, BallotError> {
+                let ciphertext =
+                    RUNTIME.encrypt(value.into(), public_key).map_err(BallotError::encrypt)?;
+                Self::from_ciphertext(&ciphertext)
+            }
+
+            pub fn decrypt(&self, private_key: &PrivateKey) -> Result<$plaintext, BallotError> {
+                RUNTIME.decrypt(&self.to_ciphertext()?, private_key).map_err(BallotError::decrypt)
+            }
+
+            pub fn from_ciphertext(ciphertext: &Ciphertext) -> Result<Self, BallotError> {
+                Ok(Self(ciphertext.as_bytes().map_err(BallotError::serialization)?))
+            }
+
+            pub fn to_ciphertext(&self) -> Result<Ciphertext, BallotError> {
+                Ciphertext::from_bytes(&self.0).map_err(BallotError::serialization)
+            }
+
+            pub fn as_bytes(&self) -> &Bytes {
+                &self.0
+            }
+
+            pub fn into_bytes(self) -> Bytes {
+                self.0
+            }
+        }
+
+        /// Wrap bytes from the contract. Nothing is checked until the ciphertext is decrypted.
+        impl From<Bytes> for $name {
+            fn from(bytes: Bytes) -> Self {
+                Self(bytes)
+            }
+        }
+
+        impl From<$name> for Bytes {
+            fn from(encrypted: $name) -> Self {
+                encrypted.0
+            }
+        }
+    };
+}
+
+encrypted! {
+    /// An encrypted `Unsigned64`, as cast in a vote.
+    EncUint64 => Unsigned64
+}
+
+encrypted! {
+    /// An encrypted `Unsigned256`, as returned by `getProposalTallys` and cast as a packed vote.
+    EncUint256 => Unsigned256
+}
+
+encrypted! {
+    /// An encrypted `Signed`, for the precompiles' `int64` operations.
+    EncInt64 => Signed
+}
+
+encrypted! {
+    /// An encrypted `Fractional<64>`, for the precompiles' `frac64` operations.
+    EncFrac64 => Fractional<64>
+}
+
+/// One voter's encrypted votes on a ballot, in whichever layout the ballot counts them in.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum EncVotes {
+    /// One vote per proposal
+    PerProposal(Vec<EncUint64>),
+    /// Every proposal's vote in one ciphertext, for a ballot deployed with a
+    /// [`Packing`](crate::Packing)
+    Packed(EncUint256),
+}
+
+impl EncVotes {
+    /// Encrypt one vote per proposal under `public_key`, packed into `slot_bits` wide slots, or
+    /// one by one if `slot_bits` is 0.
+    pub fn encrypt(
+        votes: &[u64],
+        slot_bits: u8,
+        public_key: &PublicKey,
+    ) -> Result<Self, BallotError> {
+        if slot_bits == 0 {
+            let votes = votes.iter().map(|&vote| EncUint64::encrypt(vote, public_key));
+            return Ok(Self::PerProposal(votes.collect::<Result<_, _>>()?));
+        }
+        let packed = packing::to_unsigned(packing::pack(votes, slot_bits)?);
+        Ok(Self::Packed(EncUint256::encrypt(packed, public_key)?))
+    }
+
+    /// The `bytes[] votes` argument of `Ballot.vote`.
+    pub fn into_bytes(self) -> Vec<Bytes> {
+        match self {
+            Self::PerProposal(votes) => votes.into_iter().map(Bytes::from).collect(),
+            Self::Packed(packed) => vec![packed.into_bytes()],
+        }
+    }
+}
@@ -0,0 +1,373 @@
+use std::{
+    str::FromStr,
+    sync::{Arc, RwLock},
+};
+
+use bindings::ballot::Ballot;
+use ethers::{
+    signers::Signer,
+    types::{Address, H256},
+};
+use futures::StreamExt;
+use sunscreen::PublicKey;
+use sunscreen_web3::AsBytes;
+
+use crate::{
+    relay, runtime, sign_vote, voter_proof, Allowlist, BallotBackend, BallotError,
+    BallotEventListener, EncVotes, EventSubscription, KeyStore, MockChain, NetworkConfig,
+    ParamsReport, Phase, SignedVote, TallyCache, TxOutcome, VotingRule,
+};
+
+impl BallotClient {
+    /// A client for any backend, e.g. a [`MockBallot`](crate::MockBallot) in tests.
+    pub fn with_backend(keys: KeyStore, backend: Arc<dyn BallotBackend>) -> Arc<Self> {
+        Arc::new(Self {
+            keys,
+            backend,
+            allowlist: RwLock::new(None),
+            tallies: TallyCache::default(),
+        })
+    }
+
+    fn with_keys(
+        keys: KeyStore,
+        network: NetworkConfig,
+        contract_address: &str,
+    ) -> Result<Arc<Self>, BallotError> {
+        network.ensure_fhe_precompiles()?;
+        let contract_address = Address::from_str(contract_address).map_err(BallotError::address)?;
+        let ballot = Ballot::new(contract_address, keys.client(&network)?);
+        Ok(Self::with_backend(keys, Arc::new(ballot)))
+    }
+
+    async fn network_key(&self) -> Result<PublicKey, BallotError> {
+        network_key(&*self.backend, &self.keys.public_key).await
+    }
+
+    /// The proof the ballot needs to accept this wallet's vote, from the allowlist if it has one.
+    fn voter_proof(&self, voter_root: H256) -> Result<Vec<H256>, BallotError> {
+        let allowlist = self.allowlist.read().map_err(BallotError::runtime)?;
+        voter_proof(voter_root, self.keys.wallet.address(), allowlist.as_deref())
+    }
+
+    async fn encrypt_votes(
+        &self,
+        votes: Vec<u64>,
+        rule: VotingRule,
+    ) -> Result<EncVotes, BallotError> {
+        encrypt_votes(&*self.backend, &self.keys.public_key, votes, rule).await
+    }
+
+    /// Check and encrypt `votes`, and sign them for a relayer to submit.
+    async fn signed_vote(
+        &self,
+        votes: Vec<u64>,
+        rule: VotingRule,
+    ) -> Result<SignedVote, BallotError> {
+        let proof = self.voter_proof(self.backend.voter_root().await?)?;
+        let encrypted = self.encrypt_votes(votes, rule).await?;
+        sign_vote(&self.keys.wallet, &*self.backend, encrypted, proof).await
+    }
+}
+
+/// Fetch the ballot's network public key.
+pub async fn fetch_network_key(backend: &dyn BallotBackend) -> Result<PublicKey, BallotError> {
+    let public_key_bytes = backend.get_public_key().await?;
+    PublicKey::from_bytes(&public_key_bytes).map_err(BallotError::key)
+}
+
+/// Fetch the ballot's network key and check it, and the local key, against the runtime.
+pub async fn network_key(
+    backend: &dyn BallotBackend,
+    local_key: &PublicKey,
+) -> Result<PublicKey, BallotError> {
+    let network_key = fetch_network_key(backend).await?;
+    ParamsReport::new(local_key, &network_key).ensure_compatible()?;
+    Ok(network_key)
+}
+
+/// Check that the ballot is open and `votes` satisfy `rule`, and encrypt them under the ballot's
+/// network key, packed if the ballot is.
+pub async fn encrypt_votes(
+    backend: &dyn BallotBackend,
+    local_key: &PublicKey,
+    votes: Vec<u64>,
+    rule: VotingRule,
+) -> Result<EncVotes, BallotError> {
+    Phase::Voting.ensure(backend.phase().await?, "vote")?;
+
+    // Check the ballot locally; the contract only checks the vote count, and only after we've
+    // paid for all of the encryptions
+    let proposal_count = backend.get_proposals().await?.len();
+    rule.validate(&votes, proposal_count)?;
+
+    // Votes are encrypted under the network key so the contract can add them up
+    let public_key = network_key(backend, local_key).await?;
+    EncVotes::encrypt(&votes, backend.slot_bits().await?, &public_key)
+}
+
+/// A handle to a single deployed ballot.
+///
+/// The keys are parsed once when the client is constructed, so the FHE keys and wallet secret
+/// only cross the FFI boundary a single time.
+#[derive(uniffi::Object)]
+pub struct BallotClient {
+    keys: KeyStore,
+    backend: Arc<dyn BallotBackend>,
+    allowlist: RwLock<Option<Arc<Allowlist>>>,
+    /// Tallies decrypted so far, so a refresh only decrypts those a vote has changed
+    tallies: TallyCache,
+}
+
+#[uniffi::export]
+impl BallotClient {
+    #[uniffi::constructor]
+    pub fn new(
+        public_key: String,
+        private_key: String,
+        wallet_key: String,
+        network: NetworkConfig,
+        contract_address: String,
+    ) -> Result<Arc<Self>, BallotError> {
+        let keys = KeyStore::init(public_key, private_key, wallet_key)?;
+        Self::with_keys(keys, network, &contract_address)
+    }
+
+    /// Construct a client from keys sealed with [`seal_keys`](crate::seal_keys).
+    #[uniffi::constructor]
+    pub fn with_passphrase(
+        sealed_keys: Vec<u8>,
+        public_key: String,
+        passphrase: String,
+        network: NetworkConfig,
+        contract_address: String,
+    ) -> Result<Arc<Self>, BallotError> {
+        let public_key: PublicKey = serde_json::from_str(&public_key).map_err(BallotError::key)?;
+        let keys = KeyStore::unseal(&sealed_keys, public_key, &passphrase)?;
+        Self::with_keys(keys, network, &contract_address)
+    }
+
+    /// Construct a client for a ballot deployed on a [`MockChain`], which needs no network.
+    #[uniffi::constructor]
+    pub fn with_mock(
+        public_key: String,
+        private_key: String,
+        wallet_key: String,
+        chain: Arc<MockChain>,
+        contract_address: String,
+    ) -> Result<Arc<Self>, BallotError> {
+        let keys = KeyStore::init(public_key, private_key, wallet_key)?;
+        let contract_address =
+            Address::from_str(&contract_address).map_err(BallotError::address)?;
+        let ballot = chain.ballot(contract_address, keys.wallet.address());
+        Ok(Self::with_backend(keys, Arc::new(ballot)))
+    }
+
+    /// Address of the ballot contract, as lower case hex without a `0x` prefix.
+    pub fn contract_address(&self) -> String {
+        format!("{:x}", self.backend.address())
+    }
+
+    /// Address of the wallet that signs transactions.
+    pub fn wallet_address(&self) -> String {
+        format!("{:?}", self.keys.wallet.address())
+    }
+
+    /// Use `allowlist` to prove this wallet may vote, on ballots deployed with its root. Votes
+    /// pick their proof from it automatically.
+    pub fn set_allowlist(&self, allowlist: Option<Arc<Allowlist>>) -> Result<(), BallotError> {
+        *self.allowlist.write().map_err(BallotError::runtime)? = allowlist;
+        Ok(())
+    }
+
+    /// Compare the FHE parameters of the local keys, the contract's network key and the runtime.
+    ///
+    /// Unlike the other methods, this doesn't fail on a mismatch; check
+    /// [`ParamsReport::differences`].
+    pub async fn verify_params(self: Arc<Self>) -> Result<ParamsReport, BallotError> {
+        runtime::spawn(async move {
+            let network_key = fetch_network_key(&*self.backend).await?;
+            Ok(ParamsReport::new(&self.keys.public_key, &network_key))
+        })
+        .await
+    }
+
+    pub async fn add_proposal(
+        self: Arc<Self>,
+        name: String,
+        contents: String,
+    ) -> Result<TxOutcome, BallotError> {
+        runtime::spawn(async move { self.backend.add_proposal(name, contents).await }).await
+    }
+
+    pub async fn get_proposals(self: Arc<Self>) -> Result<Vec<String>, BallotError> {
+        runtime::spawn(async move {
+            let proposals = self.backend.get_proposals().await?;
+            Ok(proposals.into_iter().map(|proposal| proposal.name).collect())
+        })
+        .await
+    }
+
+    pub async fn get_proposal_tallys(self: Arc<Self>) -> Result<Vec<String>, BallotError> {
+        runtime::spawn(async move {
+            // The contract re-encrypts under our key, so a mismatch here means an undecryptable
+            // tally rather than an obvious error
+            self.network_key().await?;
+            let tallies = self
+                .tallies
+                .tallies(&*self.backend, &self.keys.public_key, &self.keys.private_key)
+                .await?;
+            Ok(tallies.iter().map(ToString::to_string).collect())
+        })
+        .await
+    }
+
+    /// Whether this client's wallet already has a vote recorded on the ballot.
+    pub async fn has_voted(self: Arc<Self>) -> Result<bool, BallotError> {
+        runtime::spawn(async move { self.backend.has_voted(self.keys.wallet.address()).await })
+            .await
+    }
+
+    /// Encrypt and cast one vote per proposal, after checking them against `rule`.
+    ///
+    /// Fails with [`BallotError::AlreadyVoted`] if the wallet has voted before; confirm with the
+    /// user and call [`BallotClient::change_vote`] instead. On a ballot with a voter allowlist,
+    /// fails with [`BallotError::NotEligible`] unless [`BallotClient::set_allowlist`] was given
+    /// one with this wallet on it.
+    pub async fn submit_votes(
+        self: Arc<Self>,
+        votes: Vec<u64>,
+        rule: VotingRule,
+    ) -> Result<TxOutcome, BallotError> {
+        runtime::spawn(async move {
+            let voter = self.keys.wallet.address();
+            if self.backend.has_voted(voter).await? {
+                return Err(BallotError::already_voted(format!(
+                    "{voter:?} has already voted on this ballot; use change_vote to replace it"
+                )));
+            }
+            let proof = self.voter_proof(self.backend.voter_root().await?)?;
+            let encrypted = self.encrypt_votes(votes, rule).await?;
+            self.backend.vote(encrypted, proof).await
+        })
+        .await
+    }
+
+    /// Replace the wallet's previous vote. The contract subtracts the old votes from each tally
+    /// before adding the new ones.
+    pub async fn change_vote(
+        self: Arc<Self>,
+        votes: Vec<u64>,
+        rule: VotingRule,
+    ) -> Result<TxOutcome, BallotError> {
+        runtime::spawn(async move {
+            let proof = self.voter_proof(self.backend.voter_root().await?)?;
+            let encrypted = self.encrypt_votes(votes, rule).await?;
+            self.backend.change_vote(encrypted, proof).await
+        })
+        .await
+    }
+
+    /// Like [`BallotClient::submit_votes`], but has the relayer at `relayer_url` submit the votes
+    /// and pay for them, so the wallet needs no funds.
+    ///
+    /// Fails with [`BallotError::AlreadyVoted`] if the wallet has voted before, unless `replace`
+    /// is set. A vote the relayer refuses fails with [`BallotError::Relay`].
+    pub async fn relay_votes(
+        self: Arc<Self>,
+        relayer_url: String,
+        votes: Vec<u64>,
+        rule: VotingRule,
+        replace: bool,
+    ) -> Result<TxOutcome, BallotError> {
+        runtime::spawn(async move {
+            let voter = self.keys.wallet.address();
+            if !replace && self.backend.has_voted(voter).await? {
+                return Err(BallotError::already_voted(format!(
+                    "{voter:?} has already voted on this ballot; relay with replace to replace it"
+                )));
+            }
+            let vote = self.signed_vote(votes, rule).await?;
+            relay(&relayer_url, &vote).await
+        })
+        .await
+    }
+
+    /// Check, encrypt and sign votes without sending them anywhere, for the app to hand to a
+    /// relayer itself. Returns the signed vote as the JSON relayers take at `POST /votes`.
+    ///
+    /// The signature is good until voting ends, and only while no other signed vote from the
+    /// wallet has been submitted in the meantime.
+    pub async fn sign_votes(
+        self: Arc<Self>,
+        votes: Vec<u64>,
+        rule: VotingRule,
+    ) -> Result<String, BallotError> {
+        runtime::spawn(async move {
+            let vote = self.signed_vote(votes, rule).await?;
+            serde_json::to_string(&vote).map_err(BallotError::serialization)
+        })
+        .await
+    }
+
+    /// Where the ballot is in its lifecycle.
+    pub async fn phase(self: Arc<Self>) -> Result<Phase, BallotError> {
+        runtime::spawn(async move { self.backend.phase().await }).await
+    }
+
+    /// When voting ends, as a Unix timestamp; 0 until voting opens.
+    pub async fn voting_ends(self: Arc<Self>) -> Result<u64, BallotError> {
+        runtime::spawn(async move { self.backend.voting_ends().await }).await
+    }
+
+    /// Stop adding proposals and accept votes until `voting_ends`, a Unix timestamp. Only the
+    /// wallet that deployed the ballot can open it.
+    pub async fn open(self: Arc<Self>, voting_ends: u64) -> Result<TxOutcome, BallotError> {
+        runtime::spawn(async move {
+            Phase::Setup.ensure(self.backend.phase().await?, "open voting")?;
+            self.backend.open(voting_ends).await
+        })
+        .await
+    }
+
+    /// Stop accepting votes and publish the results. The wallet that deployed the ballot can
+    /// close it at any time, anyone else only once voting has ended.
+    pub async fn close(self: Arc<Self>) -> Result<TxOutcome, BallotError> {
+        runtime::spawn(async move {
+            Phase::Voting.ensure(self.backend.phase().await?, "close voting")?;
+            self.backend.close().await
+        })
+        .await
+    }
+
+    /// The final tally of each proposal, in proposal order, once voting has closed.
+    pub async fn get_results(self: Arc<Self>) -> Result<Vec<u64>, BallotError> {
+        runtime::spawn(async move {
+            Phase::Closed.ensure(self.backend.phase().await?, "read the results")?;
+            self.backend.get_results().await
+        })
+        .await
+    }
+
+    /// Deliver the ballot's events to `listener` as they happen, starting with any emitted since
+    /// `from_block`.
+    pub fn watch(
+        self: Arc<Self>,
+        from_block: Option<u64>,
+        listener: Arc<dyn BallotEventListener>,
+    ) -> Result<Arc<EventSubscription>, BallotError> {
+        let task = runtime::spawn_detached(async move {
+            let mut events = match self.backend.events(from_block).await {
+                Ok(events) => events,
+                Err(e) => return listener.on_error(e),
+            };
+            while let Some(event) = events.next().await {
+                match event {
+                    Ok(event) => listener.on_event(event),
+                    Err(e) => return listener.on_error(e),
+                }
+            }
+        })?;
+        Ok(EventSubscription::new(task))
+    }
+}
@@ -0,0 +1,172 @@
+use std::fmt::Display;
+
+use ethers::{contract::ContractError, providers::Middleware};
+
+/// Errors surfaced across the FFI boundary.
+///
+/// Each variant carries a human readable `reason` so the host app can log or display it; the
+/// variant itself tells the caller which step failed.
+#[derive(Debug, thiserror::Error, uniffi::Error)]
+pub enum BallotError {
+    /// A wallet key or FHE key could not be parsed.
+    #[error("invalid key: {reason}")]
+    Key { reason: String },
+
+    /// Encrypted keys could not be opened, usually because the passphrase is wrong.
+    #[error("could not unlock keys: {reason}")]
+    Passphrase { reason: String },
+
+    /// A contract address could not be parsed.
+    #[error("invalid address: {reason}")]
+    Address { reason: String },
+
+    /// A network profile could not be found or parsed.
+    #[error("invalid network config: {reason}")]
+    Config { reason: String },
+
+    /// The RPC node could not be reached or returned an error.
+    #[error("rpc error: {reason}")]
+    Rpc { reason: String },
+
+    /// The contract reverted the call or transaction.
+    #[error("contract reverted: {reason}")]
+    Revert { reason: String },
+
+    /// The relayer could not be reached, or refused to submit a signed vote.
+    #[error("relayer error: {reason}")]
+    Relay { reason: String },
+
+    /// A ballot was rejected by its voting rule before being encrypted.
+    #[error("invalid vote: {reason}")]
+    InvalidVote { reason: String },
+
+    /// The wallet already has a vote recorded; replacing it has to be asked for explicitly.
+    #[error("already voted: {reason}")]
+    AlreadyVoted { reason: String },
+
+    /// A voter roll or allowlist could not be read, or doesn't match the ballot.
+    #[error("invalid allowlist: {reason}")]
+    Allowlist { reason: String },
+
+    /// The wallet isn't on the ballot's voter allowlist.
+    #[error("not eligible to vote: {reason}")]
+    NotEligible { reason: String },
+
+    /// The ballot isn't in the phase the operation needs, e.g. voting before it has opened.
+    #[error("wrong ballot phase: {reason}")]
+    WrongPhase { reason: String },
+
+    /// A plaintext could not be encrypted.
+    #[error("encryption failed: {reason}")]
+    Encrypt { reason: String },
+
+    /// A ciphertext could not be decrypted.
+    #[error("decryption failed: {reason}")]
+    Decrypt { reason: String },
+
+    /// The local keys, the contract's network key and the client runtime disagree on FHE
+    /// parameters.
+    #[error("incompatible FHE parameters: {reason}")]
+    Params { reason: String },
+
+    /// A homomorphic computation could not be compiled or run.
+    #[error("FHE evaluation failed: {reason}")]
+    Fhe { reason: String },
+
+    /// A value could not be (de)serialized.
+    #[error("serialization failed: {reason}")]
+    Serialization { reason: String },
+
+    /// The shared async runtime could not be started, or shut down mid-call.
+    #[error("runtime error: {reason}")]
+    Runtime { reason: String },
+}
+
+impl BallotError {
+    pub(crate) fn key(e: impl Display) -> Self {
+        Self::Key { reason: e.to_string() }
+    }
+
+    pub(crate) fn passphrase(e: impl Display) -> Self {
+        Self::Passphrase { reason: e.to_string() }
+    }
+
+    pub(crate) fn address(e: impl Display) -> Self {
+        Self::Address { reason: e.to_string() }
+    }
+
+    pub(crate) fn config(e: impl Display) -> Self {
+        Self::Config { reason: e.to_string() }
+    }
+
+    pub(crate) fn revert(e: impl Display) -> Self {
+        Self::Revert { reason: e.to_string() }
+    }
+
+    pub(crate) fn rpc(e: impl Display) -> Self {
+        Self::Rpc { reason: e.to_string() }
+    }
+
+    pub(crate) fn relay(e: impl Display) -> Self {
+        Self::Relay { reason: e.to_string() }
+    }
+
+    pub(crate) fn invalid_vote(e: impl Display) -> Self {
+        Self::InvalidVote { reason: e.to_string() }
+    }
+
+    pub(crate) fn already_voted(e: impl Display) -> Self {
+        Self::AlreadyVoted { reason: e.to_string() }
+    }
+
+    pub(crate) fn allowlist(e: impl Display) -> Self {
+        Self::Allowlist { reason: e.to_string() }
+    }
+
+    pub(crate) fn not_eligible(e: impl Display) -> Self {
+        Self::NotEligible { reason: e.to_string() }
+    }
+
+    pub(crate) fn wrong_phase(e: impl Display) -> Self {
+        Self::WrongPhase { reason: e.to_string() }
+    }
+
+    pub(crate) fn encrypt(e: impl Display) -> Self {
+        Self::Encrypt { reason: e.to_string() }
+    }
+
+    pub(crate) fn decrypt(e: impl Display) -> Self {
+        Self::Decrypt { reason: e.to_string() }
+    }
+
+    pub(crate) fn params(e: impl Display) -> Self {
+        Self::Params { reason: e.to_string() }
+    }
+
+    pub(crate) fn fhe(e: impl Display) -> Self {
+        Self::Fhe { reason: e.to_string() }
+    }
+
+    pub(crate) fn serialization(e: impl Display) -> Self {
+        Self::Serialization { reason: e.to_string() }
+    }
+
+    pub(crate) fn runtime(e: impl Display) -> Self {
+        Self::Runtime { reason: e.to_string() }
+    }
+}
+
+impl<M: Middleware> From<ContractError<M>> for BallotError {
+    fn from(e: ContractError<M>) -> Self {
+        // Reverts are the caller's problem (wrong vote count, etc.), everything else is the
+        // node's
+        if e.is_revert() {
+            let reason = e
+                .decode_revert::<String>()
+                .unwrap_or_else(|| e.as_revert().map(|data| data.to_string()).unwrap_or_default());
+            Self::Revert { reason }
+        } else {
+            Self::rpc(e)
+        }
+    }
+}
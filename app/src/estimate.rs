@@ -0,0 +1,297 @@
+//! Gas and cost estimates for running a ballot.
+//!
+//! FHE operations are charged by the precompiles at fixed rates, which the `FHE` contract
+//! publishes as constants (`ADD_GAS`, `REENCRYPT_GAS`, ...). The rest of a transaction's gas goes
+//! to ordinary EVM work, which for a ballot is dominated by storing and copying ciphertexts. The
+//! estimate below predicts both from the ciphertext size and the proposal count, so a ballot can
+//! be sized before it is deployed, either way: with a count per proposal, or
+//! [packed](crate::Packing) into one. Given a deployed ballot, it also asks the node's
+//! `eth_estimateGas` for comparison.
+//!
+//! The constants are read without deploying anything: the `FHE` contract's code is placed at a
+//! throwaway address with an `eth_call` state override.
+
+use std::{str::FromStr, sync::Arc};
+
+use bindings::{
+    ballot::Ballot,
+    fhe::{FHE, FHE_DEPLOYED_BYTECODE},
+};
+use ethers::{
+    abi::Detokenize,
+    contract::builders::ContractCall,
+    providers::{Middleware, RawCall},
+    signers::LocalWallet,
+    types::{spoof, Address, Bytes, U256},
+};
+use sunscreen::PublicKey;
+use sunscreen_web3::AsBytes;
+
+use crate::{runtime, BallotError, EncUint256, EncUint64, NetworkConfig, Phase};
+
+// Approximate EVM gas schedule for the non-FHE parts of the ballot
+const TX_GAS: u64 = 21_000;
+const CALLDATA_BYTE_GAS: u64 = 16;
+const COLD_CALL_GAS: u64 = 2_600;
+const SLOAD_GAS: u64 = 2_100;
+const SSTORE_SET_GAS: u64 = 22_100;
+const SSTORE_RESET_GAS: u64 = 5_000;
+const EVENT_GAS: u64 = 2_000;
+
+/// 32 byte words needed for `len` bytes.
+fn words(len: u64) -> u64 {
+    len.div_ceil(32)
+}
+
+/// Calldata bytes of a dynamic `bytes` or `string` argument: offset, length and padded contents.
+fn abi_bytes(len: u64) -> u64 {
+    (2 + words(len)) * 32
+}
+
+/// Storage slots of a `bytes` or `string` value: its length slot plus its contents.
+fn slots(len: u64) -> u64 {
+    1 + words(len)
+}
+
+/// Gas the precompiles charge per operation, as published by the `FHE` contract.
+#[derive(Clone, Debug, uniffi::Record)]
+pub struct FheGas {
+    pub add: u64,
+    pub subtract: u64,
+    pub encrypt: u64,
+    pub reencrypt: u64,
+    pub network_public_key: u64,
+}
+
+/// Predicted gas for one ballot function.
+#[derive(Clone, Debug, uniffi::Record)]
+pub struct GasEstimate {
+    /// Name of the contract function
+    pub function: String,
+    /// Gas charged by the FHE precompiles
+    pub fhe_gas: u64,
+    /// Predicted gas for everything else: intrinsic, calldata, storage and calls
+    pub evm_gas: u64,
+    /// What `eth_estimateGas` reports for the same call on the deployed ballot, if one was given
+    pub node_gas: Option<u64>,
+}
+
+impl GasEstimate {
+    pub fn total_gas(&self) -> u64 {
+        self.fhe_gas + self.evm_gas
+    }
+}
+
+/// Predicted gas and cost of running a ballot with `proposal_count` proposals.
+#[derive(Clone, Debug, uniffi::Record)]
+pub struct BallotEstimate {
+    pub proposal_count: u64,
+    /// Size in bytes of one ciphertext under the network key
+    pub ciphertext_size: u64,
+    /// Current gas price, in wei
+    pub gas_price: u64,
+    pub fhe_gas: FheGas,
+    /// `add_proposal`, `vote`, `change_vote` and `get_proposal_tallys`, in that order
+    pub estimates: Vec<GasEstimate>,
+    /// The same functions on a [packed](crate::Packing) ballot
+    pub packed_estimates: Vec<GasEstimate>,
+}
+
+impl BallotEstimate {
+    /// Cost of `estimate`'s predicted gas at the current gas price, in wei.
+    pub fn cost(&self, estimate: &GasEstimate) -> U256 {
+        U256::from(estimate.total_gas()) * U256::from(self.gas_price)
+    }
+}
+
+fn gas(value: U256) -> Result<u64, BallotError> {
+    u64::try_from(value).map_err(|_| BallotError::rpc(format!("gas value {value} is out of range")))
+}
+
+/// Run `call` against the state override rather than deployed code.
+async fn call_with<M: Middleware + 'static, D: Detokenize>(
+    call: ContractCall<M, D>,
+    state: &spoof::State,
+) -> Result<D, BallotError> {
+    Ok(call.call_raw().state(state).await?)
+}
+
+impl FheGas {
+    /// Read the gas constants, and the network public key, from the `FHE` contract's code.
+    async fn fetch<M: Middleware + 'static>(client: Arc<M>) -> Result<(Self, Bytes), BallotError> {
+        let address = Address::repeat_byte(0xfe);
+        let state = spoof::code(address, FHE_DEPLOYED_BYTECODE.clone());
+        let fhe = FHE::new(address, client);
+
+        let fhe_gas = Self {
+            add: gas(call_with(fhe.add_gas(), &state).await?)?,
+            subtract: gas(call_with(fhe.subtract_gas(), &state).await?)?,
+            encrypt: gas(call_with(fhe.encrypt_gas(), &state).await?)?,
+            reencrypt: gas(call_with(fhe.reencrypt_gas(), &state).await?)?,
+            network_public_key: gas(call_with(fhe.network_public_key_gas(), &state).await?)?,
+        };
+        let network_key = call_with(fhe.network_public_key(), &state).await?;
+        Ok((fhe_gas, network_key))
+    }
+}
+
+/// `vote`, `change_vote` and `get_proposal_tallys` for a ballot with `n` encrypted counts of `ct`
+/// bytes each, under a network key of `pk` bytes.
+fn count_estimates(fhe_gas: &FheGas, n: u64, ct: u64, pk: u64) -> [GasEstimate; 3] {
+    // Every vote reads and rewrites each tally; a first vote also stores the ballot fresh
+    let vote_calldata = CALLDATA_BYTE_GAS * (4 + 64 + n * (32 + abi_bytes(ct)));
+    let tally_updates = n * slots(ct) * (SLOAD_GAS + SSTORE_RESET_GAS);
+    let vote = GasEstimate {
+        function: "vote".into(),
+        fhe_gas: fhe_gas.network_public_key + n * fhe_gas.add,
+        evm_gas: TX_GAS
+            + vote_calldata
+            + COLD_CALL_GAS
+            + tally_updates
+            + SSTORE_SET_GAS * (2 + n * slots(ct))
+            + EVENT_GAS,
+        node_gas: None,
+    };
+
+    // Replacing a vote also reads the old one back and subtracts it from each tally first
+    let change_vote = GasEstimate {
+        function: "change_vote".into(),
+        fhe_gas: fhe_gas.network_public_key + n * (fhe_gas.subtract + fhe_gas.add),
+        evm_gas: TX_GAS
+            + vote_calldata
+            + COLD_CALL_GAS
+            + 2 * tally_updates
+            + n * slots(ct) * SLOAD_GAS
+            + SSTORE_RESET_GAS * (2 + n * slots(ct))
+            + EVENT_GAS,
+        node_gas: None,
+    };
+
+    let get_proposal_tallys = GasEstimate {
+        function: "get_proposal_tallys".into(),
+        fhe_gas: n * fhe_gas.reencrypt,
+        evm_gas: TX_GAS
+            + CALLDATA_BYTE_GAS * (4 + abi_bytes(pk))
+            + COLD_CALL_GAS
+            + n * slots(ct) * SLOAD_GAS,
+        node_gas: None,
+    };
+
+    [vote, change_vote, get_proposal_tallys]
+}
+
+/// Predict the gas of each ballot function for `proposal_count` proposals, using `name` and
+/// `contents` as a sample proposal.
+///
+/// If `ballot` is given, the node's `eth_estimateGas` is asked as well, for whatever the ballot's
+/// phase allows: `add_proposal` during setup, and votes while voting is open. Votes can only be
+/// estimated if `proposal_count` matches the ballot and it has no voter allowlist, and only one
+/// of `vote` and `change_vote`, depending on whether the client's wallet has voted already. The
+/// node's figures go with `packed_estimates` if the ballot is packed.
+pub async fn estimate<M: Middleware + 'static>(
+    client: Arc<M>,
+    ballot: Option<&Ballot<M>>,
+    proposal_count: u64,
+    name: &str,
+    contents: &str,
+) -> Result<BallotEstimate, BallotError> {
+    let (fhe_gas, network_key_bytes) = FheGas::fetch(client.clone()).await?;
+    let network_key = PublicKey::from_bytes(&network_key_bytes).map_err(BallotError::key)?;
+    let zero = EncUint64::encrypt(0, &network_key)?;
+    let ciphertext_size = zero.as_bytes().len() as u64;
+    let gas_price = gas(client.get_gas_price().await.map_err(BallotError::rpc)?)?;
+
+    let n = proposal_count;
+    let ct = ciphertext_size;
+    let pk = network_key_bytes.len() as u64;
+    let (name_len, contents_len) = (name.len() as u64, contents.len() as u64);
+    let proposal_gas = TX_GAS
+        + CALLDATA_BYTE_GAS * (4 + abi_bytes(name_len) + abi_bytes(contents_len))
+        + SSTORE_SET_GAS * (slots(name_len) + slots(contents_len))
+        + SSTORE_RESET_GAS
+        + EVENT_GAS;
+
+    let add_proposal = GasEstimate {
+        function: "add_proposal".into(),
+        fhe_gas: fhe_gas.encrypt,
+        evm_gas: proposal_gas + COLD_CALL_GAS + SSTORE_SET_GAS * slots(ct),
+        node_gas: None,
+    };
+    let mut estimates = vec![add_proposal];
+    estimates.extend(count_estimates(&fhe_gas, n, ct, pk));
+
+    // A packed ballot's proposals have no count of their own, only a check that they fit, and
+    // its packed count costs what a single proposal's count would
+    let add_packed_proposal = GasEstimate {
+        function: "add_proposal".into(),
+        fhe_gas: 0,
+        evm_gas: proposal_gas + SLOAD_GAS,
+        node_gas: None,
+    };
+    let mut packed_estimates = vec![add_packed_proposal];
+    packed_estimates.extend(count_estimates(&fhe_gas, 1, ct, pk));
+
+    if let Some(ballot) = ballot {
+        let packed = ballot.slot_bits().call().await? > 0;
+        let estimates = if packed { &mut packed_estimates } else { &mut estimates };
+        let phase = Phase::try_from(ballot.phase().call().await?)?;
+        if phase == Phase::Setup {
+            let call = ballot.add_proposal(name.into(), contents.into());
+            estimates[0].node_gas = Some(gas(call.estimate_gas().await?)?);
+        }
+
+        if ballot.get_proposals().call().await?.len() as u64 == n {
+            let open_to_all = ballot.voter_root().call().await? == [0; 32];
+            if phase == Phase::Voting && open_to_all {
+                let votes = if packed {
+                    vec![EncUint256::encrypt(0, &network_key)?.into_bytes()]
+                } else {
+                    vec![zero.into_bytes(); n as usize]
+                };
+                let voted = match client.default_sender() {
+                    Some(sender) => ballot.has_voted(sender).call().await?,
+                    None => false,
+                };
+                let (index, call) = if voted {
+                    (2, ballot.change_vote(votes, Vec::new()))
+                } else {
+                    (1, ballot.vote(votes, Vec::new()))
+                };
+                estimates[index].node_gas = Some(gas(call.estimate_gas().await?)?);
+            }
+
+            let call = ballot.get_proposal_tallys(network_key_bytes);
+            estimates[3].node_gas = Some(gas(call.estimate_gas().await?)?);
+        }
+    }
+
+    Ok(BallotEstimate {
+        proposal_count,
+        ciphertext_size,
+        gas_price,
+        fhe_gas,
+        estimates,
+        packed_estimates,
+    })
+}
+
+/// Predict the gas and cost of a ballot with `proposal_count` proposals, and compare with the
+/// node's estimates if `contract_address` is given.
+#[uniffi::export]
+pub async fn estimate_costs(
+    wallet_key: String,
+    network: NetworkConfig,
+    proposal_count: u64,
+    contract_address: Option<String>,
+) -> Result<BallotEstimate, BallotError> {
+    let wallet = LocalWallet::from_str(&wallet_key).map_err(BallotError::key)?;
+    let client = network.client(wallet)?;
+    let ballot = contract_address
+        .map(|address| Address::from_str(&address).map_err(BallotError::address))
+        .transpose()?
+        .map(|address| Ballot::new(address, client.clone()));
+    runtime::spawn(async move {
+        estimate(client, ballot.as_ref(), proposal_count, "Proposal", "").await
+    })
+    .await
+}
@@ -0,0 +1,165 @@
+//! Live ballot updates.
+//!
+//! The contract emits `ProposalAdded`, `VoteCast`, `VotingOpened` and `VotingClosed`; [`watch`]
+//! turns them into a stream of [`BallotEvent`]s, optionally starting with the history since a
+//! given block so a freshly started client can catch up before following new blocks.
+
+use std::{fmt, future::ready, sync::Arc};
+
+use bindings::ballot::{Ballot, BallotEvents};
+use ethers::{
+    contract::{EthLogDecode, LogMeta},
+    providers::Middleware,
+};
+use futures::{stream, Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use tokio::task::JoinHandle;
+
+use crate::BallotError;
+
+/// Something that happened on a ballot.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, uniffi::Enum)]
+pub enum BallotEvent {
+    /// A proposal was added at position `index`.
+    ProposalAdded { index: u64, name: String, block_number: u64 },
+    /// `voter` cast a vote. `replaced` is set if it replaced one they cast earlier.
+    VoteCast { voter: String, replaced: bool, block_number: u64 },
+    /// Voting opened, and ends at `voting_ends` (a Unix timestamp).
+    VotingOpened { voting_ends: u64, block_number: u64 },
+    /// Voting closed, with `results` holding each proposal's final tally.
+    VotingClosed { results: Vec<u64>, block_number: u64 },
+}
+
+impl BallotEvent {
+    fn new(event: BallotEvents, meta: &LogMeta) -> Self {
+        let block_number = meta.block_number.as_u64();
+        match event {
+            BallotEvents::ProposalAddedFilter(event) => {
+                Self::ProposalAdded { index: event.index.as_u64(), name: event.name, block_number }
+            }
+            BallotEvents::VoteCastFilter(event) => Self::VoteCast {
+                voter: format!("{:?}", event.voter),
+                replaced: event.replaced,
+                block_number,
+            },
+            BallotEvents::VotingOpenedFilter(event) => {
+                Self::VotingOpened { voting_ends: event.voting_ends.low_u64(), block_number }
+            }
+            BallotEvents::VotingClosedFilter(event) => {
+                Self::VotingClosed { results: event.results, block_number }
+            }
+        }
+    }
+
+    pub fn block_number(&self) -> u64 {
+        match self {
+            Self::ProposalAdded { block_number, .. }
+            | Self::VoteCast { block_number, .. }
+            | Self::VotingOpened { block_number, .. }
+            | Self::VotingClosed { block_number, .. } => *block_number,
+        }
+    }
+}
+
+impl fmt::Display for BallotEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ProposalAdded { index, name, block_number } => {
+                write!(f, "[block {block_number}] proposal {index} added: {name}")
+            }
+            Self::VoteCast { voter, replaced: false, block_number } => {
+                write!(f, "[block {block_number}] {voter} voted")
+            }
+            Self::VoteCast { voter, replaced: true, block_number } => {
+                write!(f, "[block {block_number}] {voter} changed their vote")
+            }
+            Self::VotingOpened { voting_ends, block_number } => {
+                write!(f, "[block {block_number}] voting opened until {voting_ends}")
+            }
+            Self::VotingClosed { results, block_number } => {
+                write!(f, "[block {block_number}] voting closed with results {results:?}")
+            }
+        }
+    }
+}
+
+/// Stream the events of `ballot`, starting with any emitted since `from_block`.
+///
+/// Without a `from_block` only new events are streamed. The stream ends only if the node stops
+/// serving the filter.
+pub async fn watch<M: Middleware>(
+    ballot: &Ballot<M>,
+    from_block: Option<u64>,
+) -> Result<impl Stream<Item = Result<BallotEvent, BallotError>> + '_, BallotError> {
+    // Install the filter before reading the head so nothing mined in between is missed. When
+    // history is read up to the head, anything both queries see is dropped from the live half
+    // below; without history the live half is streamed whole
+    let filter = ballot.events().filter;
+    let live = ballot.client_ref().watch(&filter).await.map_err(BallotError::rpc)?;
+    let head = ballot.client_ref().get_block_number().await.map_err(BallotError::rpc)?.as_u64();
+
+    let (history, seen_through) = match from_block {
+        Some(from_block) if from_block <= head => {
+            let history =
+                ballot.events().from_block(from_block).to_block(head).query_with_meta().await?;
+            (history, Some(head))
+        }
+        _ => (Vec::new(), None),
+    };
+    let history = history.into_iter().map(|(event, meta)| Ok(BallotEvent::new(event, &meta)));
+
+    let live = live
+        .filter(move |log| {
+            let seen = match (seen_through, log.block_number) {
+                (Some(head), Some(number)) => number.as_u64() <= head,
+                _ => false,
+            };
+            ready(!seen)
+        })
+        .map(|log| {
+            let meta = LogMeta::from(&log);
+            let event =
+                BallotEvents::decode_log(&log.into()).map_err(BallotError::serialization)?;
+            Ok(BallotEvent::new(event, &meta))
+        });
+
+    Ok(stream::iter(history).chain(live))
+}
+
+/// Receives events from [`BallotClient::watch`](crate::BallotClient::watch).
+///
+/// Callbacks run on the shared runtime's worker threads; hop to the main thread before touching
+/// UI.
+#[uniffi::export(with_foreign)]
+pub trait BallotEventListener: Send + Sync {
+    fn on_event(&self, event: BallotEvent);
+
+    /// Called if watching fails. No more events are delivered afterwards.
+    fn on_error(&self, error: BallotError);
+}
+
+/// A running [`BallotClient::watch`](crate::BallotClient::watch). Watching stops when this is
+/// cancelled or dropped.
+#[derive(uniffi::Object)]
+pub struct EventSubscription {
+    task: JoinHandle<()>,
+}
+
+impl EventSubscription {
+    pub(crate) fn new(task: JoinHandle<()>) -> Arc<Self> {
+        Arc::new(Self { task })
+    }
+}
+
+#[uniffi::export]
+impl EventSubscription {
+    pub fn cancel(&self) {
+        self.task.abort();
+    }
+}
+
+impl Drop for EventSubscription {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
@@ -0,0 +1,295 @@
+//! Typed access to the FHE precompiles.
+//!
+//! [`FHE`] exposes one function per operation, operand kind and plaintext type, all taking and
+//! returning raw `bytes`, most of them also taking the network public key. [`FheOps`] puts the
+//! [typed ciphertexts](crate::EncUint64) in front of them: `add`, `subtract` and `multiply` pick
+//! the right precompile from the operand types, and the network key is fetched once and passed
+//! along automatically. Nothing here is specific to the ballot, so other contracts can use it as
+//! is.
+
+use std::sync::Arc;
+
+use bindings::fhe::FHE;
+use ethers::{
+    abi::Detokenize,
+    contract::builders::ContractCall,
+    providers::Middleware,
+    types::{Address, Bytes, U256},
+};
+use sunscreen::PublicKey;
+use sunscreen_web3::AsBytes;
+use tokio::sync::OnceCell;
+
+use crate::{BallotError, EncFrac64, EncInt64, EncUint256, EncUint64};
+
+/// A binary operation of the precompiles.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FheOp {
+    Add,
+    Subtract,
+    Multiply,
+}
+
+/// The operands of a binary operation; at least one of them is encrypted.
+pub enum Operands<E: FheType> {
+    EncEnc(E, E),
+    EncPlain(E, E::Plain),
+    PlainEnc(E::Plain, E),
+}
+
+/// A ciphertext type the precompiles operate on, and the [`FHE`] functions for it.
+pub trait FheType: From<Bytes> + Into<Bytes> + Send + Sync {
+    /// ABI type of plaintext operands and decrypted values
+    type Plain: Detokenize + Send + Sync;
+
+    fn binary<M: Middleware>(
+        fhe: &FHE<M>,
+        op: FheOp,
+        pubk: Bytes,
+        operands: Operands<Self>,
+    ) -> ContractCall<M, Bytes>;
+
+    fn encrypt<M: Middleware>(fhe: &FHE<M>, value: Self::Plain) -> ContractCall<M, Bytes>;
+
+    fn decrypt<M: Middleware>(fhe: &FHE<M>, value: Bytes) -> ContractCall<M, Self::Plain>;
+
+    fn reencrypt<M: Middleware>(fhe: &FHE<M>, pubk: Bytes, value: Bytes) -> ContractCall<M, Bytes>;
+
+    fn refresh<M: Middleware>(fhe: &FHE<M>, value: Bytes) -> ContractCall<M, Bytes>;
+}
+
+/// Operand pairs a binary operation accepts: `self` on the left, `Rhs` on the right.
+pub trait IntoOperands<Rhs> {
+    /// The ciphertext type of the result
+    type Output: FheType;
+
+    fn into_operands(self, rhs: Rhs) -> Operands<Self::Output>;
+}
+
+macro_rules! fhe_type {
+    (
+        $enc:ty => $plain:ty,
+        add: [$add_ee:ident, $add_ep:ident, $add_pe:ident],
+        subtract: [$sub_ee:ident, $sub_ep:ident, $sub_pe:ident],
+        multiply: [$mul_ee:ident, $mul_ep:ident, $mul_pe:ident],
+        encrypt: $encrypt:ident,
+        decrypt: $decrypt:ident,
+        reencrypt: $reencrypt:ident,
+        refresh: $refresh:ident $(,)?
+    ) => {
+        impl FheType for $enc {
+            type Plain = $plain;
+
+            fn binary<M: Middleware>(
+                fhe: &FHE<M>,
+                op: FheOp,
+                pubk: Bytes,
+                operands: Operands<Self>,
+            ) -> ContractCall<M, Bytes> {
+                use Operands::*;
+                match (op, operands) {
+                    (FheOp::Add, EncEnc(a, b)) => fhe.$add_ee(pubk, a.into(), b.into()),
+                    (FheOp::Add, EncPlain(a, b)) => fhe.$add_ep(pubk, a.into(), b),
+                    (FheOp::Add, PlainEnc(a, b)) => fhe.$add_pe(pubk, a, b.into()),
+                    (FheOp::Subtract, EncEnc(a, b)) => fhe.$sub_ee(pubk, a.into(), b.into()),
+                    (FheOp::Subtract, EncPlain(a, b)) => fhe.$sub_ep(pubk, a.into(), b),
+                    (FheOp::Subtract, PlainEnc(a, b)) => fhe.$sub_pe(pubk, a, b.into()),
+                    (FheOp::Multiply, EncEnc(a, b)) => fhe.$mul_ee(pubk, a.into(), b.into()),
+                    (FheOp::Multiply, EncPlain(a, b)) => fhe.$mul_ep(pubk, a.into(), b),
+                    (FheOp::Multiply, PlainEnc(a, b)) => fhe.$mul_pe(pubk, a, b.into()),
+                }
+            }
+
+            fn encrypt<M: Middleware>(fhe: &FHE<M>, value: $plain) -> ContractCall<M, Bytes> {
+                fhe.$encrypt(value)
+            }
+
+            fn decrypt<M: Middleware>(fhe: &FHE<M>, value: Bytes) -> ContractCall<M, $plain> {
+                fhe.$decrypt(value)
+            }
+
+            fn reencrypt<M: Middleware>(
+                fhe: &FHE<M>,
+                pubk: Bytes,
+                value: Bytes,
+            ) -> ContractCall<M, Bytes> {
+                fhe.$reencrypt(pubk, value)
+            }
+
+            fn refresh<M: Middleware>(fhe: &FHE<M>, value: Bytes) -> ContractCall<M, Bytes> {
+                fhe.$refresh(value)
+            }
+        }
+
+        impl IntoOperands<$enc> for $enc {
+            type Output = $enc;
+
+            fn into_operands(self, rhs: $enc) -> Operands<$enc> {
+                Operands::EncEnc(self, rhs)
+            }
+        }
+
+        impl IntoOperands<$plain> for $enc {
+            type Output = $enc;
+
+            fn into_operands(self, rhs: $plain) -> Operands<$enc> {
+                Operands::EncPlain(self, rhs)
+            }
+        }
+
+        impl IntoOperands<$enc> for $plain {
+            type Output = $enc;
+
+            fn into_operands(self, rhs: $enc) -> Operands<$enc> {
+                Operands::PlainEnc(self, rhs)
+            }
+        }
+    };
+}
+
+fhe_type! {
+    EncUint64 => u64,
+    add: [add_uint_64_enc_enc, add_uint_64_enc_plain, add_uint_64_plain_enc],
+    subtract: [subtract_uint_64_enc_enc, subtract_uint_64_enc_plain, subtract_uint_64_plain_enc],
+    multiply: [multiply_uint_64_enc_enc, multiply_uint_64_enc_plain, multiply_uint_64_plain_enc],
+    encrypt: encrypt_uint_64,
+    decrypt: decrypt_uint_64,
+    reencrypt: reencrypt_uint_64,
+    refresh: refresh_uint_64,
+}
+
+fhe_type! {
+    EncUint256 => U256,
+    add: [add_uint_256_enc_enc, add_uint_256_enc_plain, add_uint_256_plain_enc],
+    subtract: [
+        subtract_uint_256_enc_enc,
+        subtract_uint_256_enc_plain,
+        subtract_uint_256_plain_enc
+    ],
+    multiply: [
+        multiply_uint_256_enc_enc,
+        multiply_uint_256_enc_plain,
+        multiply_uint_256_plain_enc
+    ],
+    encrypt: encrypt_uint_256,
+    decrypt: decrypt_uint_256,
+    reencrypt: reencrypt_uint_256,
+    refresh: refresh_uint_256,
+}
+
+fhe_type! {
+    EncInt64 => i64,
+    add: [add_int_64_enc_enc, add_int_64_enc_plain, add_int_64_plain_enc],
+    subtract: [subtract_int_64_enc_enc, subtract_int_64_enc_plain, subtract_int_64_plain_enc],
+    multiply: [multiply_int_64_enc_enc, multiply_int_64_enc_plain, multiply_int_64_plain_enc],
+    encrypt: encrypt_int_64,
+    decrypt: decrypt_int_64,
+    reencrypt: reencrypt_int_64,
+    refresh: refresh_int_64,
+}
+
+// The precompiles take and return `frac64` plaintexts in their own 8 byte encoding
+fhe_type! {
+    EncFrac64 => [u8; 8],
+    add: [add_frac_64_enc_enc, add_frac_64_enc_plain, add_frac_64_plain_enc],
+    subtract: [subtract_frac_64_enc_enc, subtract_frac_64_enc_plain, subtract_frac_64_plain_enc],
+    multiply: [
+        multiply_frac_64_enc_enc,
+        multiply_frac_64_enc_plain,
+        multiply_frac_64_plain_enc
+    ],
+    encrypt: encrypt_frac_64,
+    decrypt: decrypt_frac_64,
+    reencrypt: reencrypt_frac_64,
+    refresh: refresh_frac_64,
+}
+
+/// An [`FHE`] contract, with typed operations and the network key handled for you.
+pub struct FheOps<M> {
+    fhe: FHE<M>,
+    network_key: OnceCell<Bytes>,
+}
+
+impl<M: Middleware + 'static> FheOps<M> {
+    /// Use the `FHE` contract deployed at `address`.
+    pub fn new(address: Address, client: Arc<M>) -> Self {
+        Self { fhe: FHE::new(address, client), network_key: OnceCell::new() }
+    }
+
+    /// Deploy a new `FHE` contract.
+    pub async fn deploy(client: Arc<M>) -> Result<Self, BallotError> {
+        let fhe = FHE::deploy(client, ())?.send().await?;
+        Ok(Self { fhe, network_key: OnceCell::new() })
+    }
+
+    pub fn address(&self) -> Address {
+        self.fhe.address()
+    }
+
+    /// The network public key, fetched on first use.
+    pub async fn network_key_bytes(&self) -> Result<Bytes, BallotError> {
+        let network_key = self
+            .network_key
+            .get_or_try_init(|| async { self.fhe.network_public_key().call().await })
+            .await?;
+        Ok(network_key.clone())
+    }
+
+    /// The network public key, for encrypting locally with e.g. [`EncUint64::encrypt`].
+    pub async fn network_key(&self) -> Result<PublicKey, BallotError> {
+        PublicKey::from_bytes(&self.network_key_bytes().await?).map_err(BallotError::key)
+    }
+
+    async fn binary<E: FheType>(&self, op: FheOp, operands: Operands<E>) -> Result<E, BallotError> {
+        let pubk = self.network_key_bytes().await?;
+        Ok(E::binary(&self.fhe, op, pubk, operands).call().await?.into())
+    }
+
+    pub async fn add<A: IntoOperands<B>, B>(&self, a: A, b: B) -> Result<A::Output, BallotError> {
+        self.binary(FheOp::Add, a.into_operands(b)).await
+    }
+
+    pub async fn subtract<A: IntoOperands<B>, B>(
+        &self,
+        a: A,
+        b: B,
+    ) -> Result<A::Output, BallotError> {
+        self.binary(FheOp::Subtract, a.into_operands(b)).await
+    }
+
+    pub async fn multiply<A: IntoOperands<B>, B>(
+        &self,
+        a: A,
+        b: B,
+    ) -> Result<A::Output, BallotError> {
+        self.binary(FheOp::Multiply, a.into_operands(b)).await
+    }
+
+    /// Encrypt `value` under the network key.
+    ///
+    /// This sends the plaintext to the node; encrypt locally under [`FheOps::network_key`] to
+    /// keep it private.
+    pub async fn encrypt<E: FheType>(&self, value: E::Plain) -> Result<E, BallotError> {
+        Ok(E::encrypt(&self.fhe, value).call().await?.into())
+    }
+
+    /// Decrypt `value` with the network key.
+    pub async fn decrypt<E: FheType>(&self, value: E) -> Result<E::Plain, BallotError> {
+        Ok(E::decrypt(&self.fhe, value.into()).call().await?)
+    }
+
+    /// Re-encrypt `value` from the network key to `public_key`.
+    pub async fn reencrypt<E: FheType>(
+        &self,
+        value: E,
+        public_key: &PublicKey,
+    ) -> Result<E, BallotError> {
+        let pubk = public_key.as_bytes().map_err(BallotError::serialization)?;
+        Ok(E::reencrypt(&self.fhe, pubk, value.into()).call().await?.into())
+    }
+
+    /// Re-encrypt `value` under the network key, resetting its noise budget.
+    pub async fn refresh<E: FheType>(&self, value: E) -> Result<E, BallotError> {
+        Ok(E::refresh(&self.fhe, value.into()).call().await?.into())
+    }
+}
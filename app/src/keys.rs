@@ -0,0 +1,176 @@
+use std::{fs, path::Path, str::FromStr, sync::Arc};
+
+use ethers::{prelude::rand::thread_rng, signers::LocalWallet, utils::hex};
+use serde::{Deserialize, Serialize};
+use sunscreen::{PrivateKey, PublicKey};
+use sunscreen_web3::{testnet::parasol::generate_keys, AsFile};
+use zeroize::Zeroizing;
+
+use crate::{vault, BallotError, BallotMiddleware, NetworkConfig};
+
+/// A Parasol wallet and the FHE keypair used to decrypt tallies.
+pub struct KeyStore {
+    pub wallet: LocalWallet,
+    pub public_key: PublicKey,
+    pub private_key: PrivateKey,
+}
+
+/// The secret half of a [`KeyStore`], which is what gets encrypted at rest.
+#[derive(Serialize, Deserialize)]
+struct Secrets {
+    wallet_key: String,
+    private_key: PrivateKey,
+}
+
+impl Secrets {
+    fn new(wallet: &LocalWallet, private_key: PrivateKey) -> Self {
+        Self { wallet_key: hex::encode(wallet.signer().to_bytes()), private_key }
+    }
+
+    fn seal(&self, passphrase: &str) -> Result<Vec<u8>, BallotError> {
+        let plaintext =
+            Zeroizing::new(serde_json::to_vec(self).map_err(BallotError::serialization)?);
+        vault::seal(&plaintext, passphrase)
+    }
+}
+
+impl KeyStore {
+    const WALLET_PATH: &'static str = "wallet.sk";
+    const PRIVATE_KEY_PATH: &'static str = "fhe.pri";
+    const PUBLIC_KEY_PATH: &'static str = "fhe.pub";
+    const SEALED_PATH: &'static str = "secrets.enc";
+
+    /// Generate a fresh wallet and FHE keypair.
+    pub fn generate() -> Result<Self, BallotError> {
+        let (public_key, private_key) = generate_keys().map_err(BallotError::key)?;
+        let wallet = LocalWallet::new(&mut thread_rng());
+        Ok(Self { wallet, public_key, private_key })
+    }
+
+    /// Parse keys passed as JSON (FHE keys) and hex (wallet key).
+    pub(crate) fn init(
+        public_key: String,
+        private_key: String,
+        wallet_key: String,
+    ) -> Result<Self, BallotError> {
+        let public_key: PublicKey = serde_json::from_str(&public_key).map_err(BallotError::key)?;
+        let private_key: PrivateKey =
+            serde_json::from_str(&private_key).map_err(BallotError::key)?;
+        let wallet = LocalWallet::from_str(&wallet_key).map_err(BallotError::key)?;
+        Ok(Self { wallet, public_key, private_key })
+    }
+
+    /// Encrypt the wallet key and FHE private key under `passphrase`.
+    ///
+    /// The FHE public key is not secret and is not included.
+    pub fn seal(&self, passphrase: &str) -> Result<Vec<u8>, BallotError> {
+        Secrets::new(&self.wallet, self.private_key.clone()).seal(passphrase)
+    }
+
+    /// Decrypt secrets produced by [`KeyStore::seal`].
+    pub fn unseal(
+        sealed: &[u8],
+        public_key: PublicKey,
+        passphrase: &str,
+    ) -> Result<Self, BallotError> {
+        let plaintext = vault::open(sealed, passphrase)?;
+        let secrets: Secrets =
+            serde_json::from_slice(&plaintext).map_err(BallotError::serialization)?;
+        let wallet = LocalWallet::from_str(&secrets.wallet_key).map_err(BallotError::key)?;
+        Ok(Self { wallet, public_key, private_key: secrets.private_key })
+    }
+
+    /// Whether the key store in `dir` is passphrase protected.
+    pub fn is_sealed(dir: &Path) -> bool {
+        dir.join(Self::SEALED_PATH).exists()
+    }
+
+    /// Whether `dir` holds any keys at all.
+    pub fn exists(dir: &Path) -> bool {
+        [Self::WALLET_PATH, Self::PRIVATE_KEY_PATH, Self::PUBLIC_KEY_PATH, Self::SEALED_PATH]
+            .into_iter()
+            .any(|file| dir.join(file).exists())
+    }
+
+    /// Read keys from `dir`. `passphrase` is required if the key store is sealed.
+    pub fn read(dir: &Path, passphrase: Option<&str>) -> Result<Self, BallotError> {
+        let public_key =
+            PublicKey::read(dir.join(Self::PUBLIC_KEY_PATH)).map_err(BallotError::key)?;
+
+        if Self::is_sealed(dir) {
+            let passphrase = passphrase.ok_or_else(|| {
+                BallotError::passphrase(
+                    "key store is passphrase protected; a passphrase is required",
+                )
+            })?;
+            let sealed = fs::read(dir.join(Self::SEALED_PATH)).map_err(BallotError::key)?;
+            return Self::unseal(&sealed, public_key, passphrase);
+        }
+
+        let private_key =
+            PrivateKey::read(dir.join(Self::PRIVATE_KEY_PATH)).map_err(BallotError::key)?;
+        let wallet = LocalWallet::read(dir.join(Self::WALLET_PATH)).map_err(BallotError::key)?;
+        Ok(Self { wallet, public_key, private_key })
+    }
+
+    /// Write keys to `dir`, sealing the secrets if a `passphrase` is given.
+    ///
+    /// Whichever layout is not written (plaintext or sealed) is removed, so a directory never
+    /// holds both.
+    pub fn write(&self, dir: &Path, passphrase: Option<&str>) -> Result<(), BallotError> {
+        self.public_key.write(dir.join(Self::PUBLIC_KEY_PATH)).map_err(BallotError::key)?;
+
+        let stale = match passphrase {
+            Some(passphrase) => {
+                fs::write(dir.join(Self::SEALED_PATH), self.seal(passphrase)?)
+                    .map_err(BallotError::key)?;
+                vec![Self::PRIVATE_KEY_PATH, Self::WALLET_PATH]
+            }
+            None => {
+                self.private_key
+                    .write(dir.join(Self::PRIVATE_KEY_PATH))
+                    .map_err(BallotError::key)?;
+                self.wallet.write(dir.join(Self::WALLET_PATH)).map_err(BallotError::key)?;
+                vec![Self::SEALED_PATH]
+            }
+        };
+
+        for file in stale {
+            let path = dir.join(file);
+            if path.exists() {
+                fs::remove_file(path).map_err(BallotError::key)?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn client(&self, network: &NetworkConfig) -> Result<Arc<BallotMiddleware>, BallotError> {
+        network.client(self.wallet.clone())
+    }
+}
+
+/// Encrypt a wallet key and FHE private key under `passphrase`, for storage at rest.
+///
+/// Pass the result to [`BallotClient::with_passphrase`](crate::BallotClient::with_passphrase)
+/// to use the keys.
+#[uniffi::export]
+pub fn seal_keys(
+    private_key: String,
+    wallet_key: String,
+    passphrase: String,
+) -> Result<Vec<u8>, BallotError> {
+    let private_key: PrivateKey = serde_json::from_str(&private_key).map_err(BallotError::key)?;
+    let wallet = LocalWallet::from_str(&wallet_key).map_err(BallotError::key)?;
+    Secrets::new(&wallet, private_key).seal(&passphrase)
+}
+
+/// Re-encrypt sealed keys under a new passphrase.
+#[uniffi::export]
+pub fn change_passphrase(
+    sealed_keys: Vec<u8>,
+    old_passphrase: String,
+    new_passphrase: String,
+) -> Result<Vec<u8>, BallotError> {
+    let plaintext = vault::open(&sealed_keys, &old_passphrase)?;
+    vault::seal(&plaintext, &new_passphrase)
+}
@@ -1,215 +1,242 @@
-mod bin;
-
-use sunscreen::{fhe_program, types::{bfv::Signed, Cipher}, Ciphertext, CompiledFheProgram, Compiler, Error, FheProgramInput, FheRuntime, Params, PrivateKey, PublicKey, Plaintext};
-
-use std::{fs, path::PathBuf, str::FromStr, sync::Arc};
-
-use bindings::ballot::{Ballot, Proposal};
-use clap::{Parser, Subcommand, ValueEnum};
+mod allowlist;
+mod audit;
+mod backend;
+mod batch;
+mod bundle;
+mod cipher;
+mod client;
+mod error;
+mod estimate;
+mod events;
+mod fhe;
+mod keys;
+mod middleware;
+mod mock;
+mod network;
+mod packing;
+mod params;
+mod phase;
+mod relay;
+mod rules;
+mod runtime;
+mod tally;
+mod tx;
+mod vault;
+
+use std::str::FromStr;
+
+use bindings::ballot::Ballot;
 use ethers::{
-    prelude::rand::thread_rng,
-    providers::{Http, Provider},
     signers::{LocalWallet, Signer},
-    types::Address,
+    types::H256,
+};
+use sunscreen_web3::testnet::parasol::generate_keys;
+
+pub use allowlist::{verify_proof, voter_proof, Allowlist};
+pub use audit::{verify_vote_call, VoteAudit};
+pub use backend::BallotBackend;
+pub use batch::{
+    add_proposals, add_proposals_many, submit_votes_many, vote_many, BallotVotes, CallOutcome,
+    NewProposal,
+};
+pub use bundle::{
+    export_key_bundle, import_key_bundle, params_fingerprint, ImportedKeys, KeyBundle,
 };
-use ethers::types::Bytes;
-use ethers::core::k256::sha2::digest::typenum::UInt;
-use ethers::utils::{__serde_json, serialize};
-use ethers::utils::__serde_json::{from_str, to_string};
-use ethers::utils::hex::ToHex;
-use eyre::{bail, Result};
-use sunscreen::types::bfv::{Unsigned, Unsigned64};
-use sunscreen::types::TryFromPlaintext;
-use sunscreen_web3::{
-    testnet::parasol::{generate_keys, PARASOL, RUNTIME},
-    AsBytes, AsFile, AsNum, SignedMiddleware, Unsigned256,
+pub use cipher::{EncFrac64, EncInt64, EncUint256, EncUint64, EncVotes};
+pub use client::{encrypt_votes, fetch_network_key, network_key, BallotClient};
+pub use error::BallotError;
+pub use estimate::{estimate, estimate_costs, BallotEstimate, FheGas, GasEstimate};
+pub use events::{watch, BallotEvent, BallotEventListener, EventSubscription};
+pub use fhe::{FheOp, FheOps, FheType, IntoOperands, Operands};
+pub use keys::{change_passphrase, seal_keys, KeyStore};
+pub use middleware::{BallotMiddleware, ResilientError, ResilientMiddleware, TxPolicy};
+pub use mock::{MockBallot, MockChain};
+pub use network::{
+    anvil_wallet_key, local_network_config, network_config, network_config_from_toml, NetworkConfig,
 };
+pub use packing::{pack, unpack, unpack_tallies, Packing};
+pub use params::ParamsReport;
+pub use phase::Phase;
+pub use relay::{relay, sign_vote, RelayError, SignedVote};
+pub use rules::{validate_votes, VotingRule};
+pub use runtime::{configure_runtime, shutdown_runtime};
+pub use tally::{decrypt_tallies, TallyCache};
+pub use tx::{TxOutcome, TxStatus};
+
+uniffi::setup_scaffolding!();
+
+#[uniffi::export]
+pub fn generate_keys_local() -> Result<Vec<String>, BallotError> {
+    let (public_key, private_key) = generate_keys().map_err(BallotError::key)?;
+    Ok(vec![
+        serde_json::to_string(&public_key).map_err(BallotError::serialization)?,
+        serde_json::to_string(&private_key).map_err(BallotError::serialization)?,
+    ])
+}
 
-enum NetworkOption {
-    /// Locally runnying Anvil node at http://localhost:8545
-    ///
-    /// If you supply this option, you probably want to supply a --wallet-key
-    /// as well, and pass in one of the Anvil account secret keys.
-     Local,
-    /// Parasol network
-    Parasol,
+/// Deploy a new ballot, whose address is the outcome's `contract_address`. `voter_root` is the
+/// root of an [`Allowlist`] to restrict voting to; anyone can vote without one. With `packing`,
+/// the ballot counts every proposal in one ciphertext; see [`Packing`]. Nothing on chain stops
+/// a packed count overflowing into the next proposal's, so packed ballots are only as sound as
+/// their voters' clients.
+#[uniffi::export]
+pub async fn deploy_contract(
+    public_key: String,
+    private_key: String,
+    wallet_key: String,
+    network: NetworkConfig,
+    voter_root: Option<String>,
+    packing: Option<Packing>,
+) -> Result<TxOutcome, BallotError> {
+    let keys = KeyStore::init(public_key, private_key, wallet_key)?;
+    let client = keys.client(&network)?;
+    let voter_root = voter_root
+        .map(|root| H256::from_str(&root).map_err(BallotError::allowlist))
+        .transpose()?
+        .unwrap_or_default();
+    let slot_bits = packing.map(|packing| packing.slot_bits()).transpose()?.unwrap_or_default();
+    runtime::spawn(async move {
+        network.probe_fhe_precompiles(client.clone()).await?;
+        let args = (voter_root.to_fixed_bytes(), slot_bits);
+        let deployer = Ballot::deploy(client.clone(), args)?;
+        let (_, receipt) = deployer.send_with_receipt().await?;
+        TxOutcome::from_receipt(&*client, receipt).await
+    })
+    .await
 }
 
-struct KeyStore {
-    wallet: LocalWallet,
-    public_key: PublicKey,
-    private_key: PrivateKey,
+#[uniffi::export]
+pub async fn add_proposal(
+    contract_address: String,
+    name: String,
+    contents: String,
+    public_key: String,
+    private_key: String,
+    wallet_key: String,
+    network: NetworkConfig,
+) -> Result<TxOutcome, BallotError> {
+    let client = BallotClient::new(public_key, private_key, wallet_key, network, contract_address)?;
+    client.add_proposal(name, contents).await
 }
 
-impl KeyStore {
-    const WALLET_PATH: &'static str = "wallet.sk";
-    const PRIVATE_KEY_PATH: &'static str = "fhe.pri";
-    const PUBLIC_KEY_PATH: &'static str = "fhe.pub";
-
-    /// Generate new keys and save them to the specified directory.
-    fn generate(parent_dir: PathBuf, force: bool) -> Result<Self> {
-        // Throw errors if necessary
-        if !force {
-            for file in [Self::WALLET_PATH, Self::PRIVATE_KEY_PATH, Self::PUBLIC_KEY_PATH] {
-                let path = parent_dir.join(file);
-                if path.exists() {
-                    bail!("{} already exists; use --force to overwrite it", path.display());
-                }
-            }
-        }
-
-        // Generate new keys
-        let (public_key, private_key) = generate_keys()?;
-        let wallet = LocalWallet::new(&mut thread_rng());
-
-        // Write keys to files
-        public_key.write(parent_dir.join(Self::PUBLIC_KEY_PATH))?;
-        private_key.write(parent_dir.join(Self::PRIVATE_KEY_PATH))?;
-        wallet.write(parent_dir.join(Self::WALLET_PATH))?;
-
-        // Log messages to the user
-        eprintln!("Saved new keys under directory {}", parent_dir.display());
-        eprintln!(
-            "Head to {}?address={:?} for some free SPETH!",
-            PARASOL.faucet_url,
-            wallet.address()
-        );
-
-        Ok(Self { wallet, public_key, private_key })
-    }
-
-    fn init(public_key: String, private_key: String, wallet_key: String) -> Result<Self> {
-        let public_key: PublicKey = serde_json::from_str (public_key.as_str()).expect("problem");
-        let private_key: PrivateKey = serde_json::from_str (private_key.as_str()).expect("problem");
-        // let public_key = PublicKey::from_str(public_key.as_str()).expect("invalid public key");
-        // let private_key = PrivateKey::from_str(private_key.as_str()).expect("invalid private key");
-        let wallet = LocalWallet::from_str(wallet_key.as_str()).expect("no wallet");
-        Ok(Self { wallet, public_key, private_key })
-    }
-
-    fn client(&self, network: NetworkOption) -> Result<Arc<SignedMiddleware>> {
-        Ok(match network {
-            NetworkOption::Local => {
-                let provider =
-                    Arc::new(Provider::<Http>::try_from("http://localhost:8545").unwrap());
-                Arc::new(SignedMiddleware::new(
-                    provider,
-                    self.wallet.clone().with_chain_id(31337_u64),
-                ))
-            }
-            NetworkOption::Parasol => PARASOL.client(self.wallet.clone()),
-        })
-    }
-
-    fn contract(
-        &self,
-        contract_address: Address,
-    ) -> Result<Ballot<SignedMiddleware>> {
-        let client = self.client(NetworkOption::Parasol)?;
-        let contract = Ballot::new(contract_address, client);
-        Ok(contract)
-    }
+#[uniffi::export]
+pub async fn get_proposals(
+    contract_address: String,
+    public_key: String,
+    private_key: String,
+    wallet_key: String,
+    network: NetworkConfig,
+) -> Result<Vec<String>, BallotError> {
+    let client = BallotClient::new(public_key, private_key, wallet_key, network, contract_address)?;
+    client.get_proposals().await
 }
 
-uniffi::setup_scaffolding!();
+#[uniffi::export]
+pub async fn get_proposal_tallys(
+    contract_address: String,
+    public_key: String,
+    private_key: String,
+    wallet_key: String,
+    network: NetworkConfig,
+) -> Result<Vec<String>, BallotError> {
+    let client = BallotClient::new(public_key, private_key, wallet_key, network, contract_address)?;
+    client.get_proposal_tallys().await
+}
 
 #[uniffi::export]
-pub fn generate_keys_local() -> Vec<String> {
-    let (public_key, private_key) = generate_keys().expect("Could not generate keys");
-
-    // let key = wallet.signer().to_bytes();
-    // let wallet_string = std::str::from_utf8(&key).expect("Could not create wallet");
-    // let private_key_bytes = private_key.as_bytes().expect("Could not parse private key");
-    // let public_key_bytes = &public_key.as_bytes().expect("Could not parse public key");
-    // let private_key_string = std::str::from_utf8(&private_key_bytes).expect("Could not create wallet");
-    // let public_key_string = std::str::from_utf8(&public_key_bytes).expect("Could not create wallet");
-    return [ serde_json::to_string(&public_key).expect(""), serde_json::to_string(&private_key).expect("") ].to_vec();
+pub async fn verify_params(
+    contract_address: String,
+    public_key: String,
+    private_key: String,
+    wallet_key: String,
+    network: NetworkConfig,
+) -> Result<ParamsReport, BallotError> {
+    let client = BallotClient::new(public_key, private_key, wallet_key, network, contract_address)?;
+    client.verify_params().await
 }
-#[tokio::main(flavor = "multi_thread", worker_threads = 10)]
+
 #[uniffi::export]
-pub async fn deploy_contract(public_key: String,
-                             private_key: String,
-                             wallet_key: String) -> String {
-    let keys = KeyStore::init(public_key, private_key, wallet_key).expect("failed to make keys");
-    let client = keys.client(NetworkOption::Parasol).expect("no client");
-    let contract_addr = Ballot::deploy(Arc::clone(&client), ()).expect("whoops").send().await.expect("no deploy").address();
-    let lower_hex = format!("{:x}", contract_addr);
-    return lower_hex;
+pub async fn has_voted(
+    contract_address: String,
+    public_key: String,
+    private_key: String,
+    wallet_key: String,
+    network: NetworkConfig,
+) -> Result<bool, BallotError> {
+    let client = BallotClient::new(public_key, private_key, wallet_key, network, contract_address)?;
+    client.has_voted().await
+}
 
+/// Open voting until `voting_ends`, a Unix timestamp.
+#[uniffi::export]
+pub async fn open_ballot(
+    contract_address: String,
+    voting_ends: u64,
+    public_key: String,
+    private_key: String,
+    wallet_key: String,
+    network: NetworkConfig,
+) -> Result<TxOutcome, BallotError> {
+    let client = BallotClient::new(public_key, private_key, wallet_key, network, contract_address)?;
+    client.open(voting_ends).await
 }
-#[tokio::main(flavor = "multi_thread", worker_threads = 10)]
+
 #[uniffi::export]
-pub async fn add_proposal(contract_address: String,
-                          name: String,
-                          contents: String,
-                          public_key: String,
-                          private_key: String,
-                          wallet_key: String) -> String {
-    let keys = KeyStore::init(public_key, private_key, wallet_key).expect("failed to make keys");
-    let ballot = keys.contract(Address::from_str(&contract_address).expect("no string")).expect("problem");
-    let result = ballot.add_proposal(name, contents).send().await.expect("test").await.expect("could not post transaction");
-    serde_json::to_string(&result).expect("pls")
+pub async fn close_ballot(
+    contract_address: String,
+    public_key: String,
+    private_key: String,
+    wallet_key: String,
+    network: NetworkConfig,
+) -> Result<TxOutcome, BallotError> {
+    let client = BallotClient::new(public_key, private_key, wallet_key, network, contract_address)?;
+    client.close().await
 }
 
-#[tokio::main(flavor = "multi_thread", worker_threads = 10)]
 #[uniffi::export]
-pub async fn get_proposals(contract_address: String,
-                          public_key: String,
-                          private_key: String,
-                          wallet_key: String) -> Vec<String> {
-    let keys = KeyStore::init(public_key, private_key, wallet_key).expect("failed to make keys");
-    let ballot = keys.contract(Address::from_str(&contract_address).expect("no string")).expect("problem");
-    let result = ballot.get_proposals().call().await.expect("pls");
-    result.into_iter().map(|x| x.name).collect::<Vec<_>>()
-
-    //serde_json::to_string(&result).expect("pls")
-    //  return "Test".to_string();
+pub async fn get_results(
+    contract_address: String,
+    public_key: String,
+    private_key: String,
+    wallet_key: String,
+    network: NetworkConfig,
+) -> Result<Vec<u64>, BallotError> {
+    let client = BallotClient::new(public_key, private_key, wallet_key, network, contract_address)?;
+    client.get_results().await
 }
 
-#[tokio::main(flavor = "multi_thread", worker_threads = 10)]
 #[uniffi::export]
-pub async fn get_proposal_tallys(contract_address: String,
-                           public_key: String,
-                           private_key: String,
-                           wallet_key: String) -> Vec<String> {
-    let keys = KeyStore::init(public_key, private_key, wallet_key).expect("failed to make keys");
-    let ballot = keys.contract(Address::from_str(&contract_address).expect("no string")).expect("problem");
-    let public_key_bytes = keys.public_key.as_bytes().expect("whoops");
-    let result = ballot.get_proposal_tallys(public_key_bytes).call().await.expect("call failed");
-    let mut value: Vec<String> = vec![];
-
-    for x in result {
-        let decrypted: Unsigned256 = RUNTIME.decrypt(&Ciphertext::from_bytes(&x).expect("no cipher"), &keys.private_key).expect("No decrypt");
-        value.push(decrypted.to_string());
-    }
-
-    return value;
+pub async fn try_wallet(private_key: String) -> Result<String, BallotError> {
+    let wallet = LocalWallet::from_str(&private_key).map_err(BallotError::key)?;
+    Ok(wallet.address().to_string())
 }
 
+/// Cast votes on a ballot anyone can vote on. For a ballot with a voter allowlist, use
+/// [`BallotClient::set_allowlist`] and [`BallotClient::submit_votes`].
 #[uniffi::export]
-pub async fn try_wallet(private_key: String) -> String {
-    let wallet = LocalWallet::from_str(private_key.as_str()).expect("Nope");
-    wallet.address().to_string()
+pub async fn submit_votes(
+    contract_address: String,
+    public_key: String,
+    private_key: String,
+    wallet_key: String,
+    network: NetworkConfig,
+    votes: Vec<u64>,
+    rule: VotingRule,
+) -> Result<TxOutcome, BallotError> {
+    let client = BallotClient::new(public_key, private_key, wallet_key, network, contract_address)?;
+    client.submit_votes(votes, rule).await
 }
 
-#[tokio::main(flavor = "multi_thread", worker_threads = 10)]
 #[uniffi::export]
-pub async fn submit_votes(contract_address: String,
-            public_key: String,
-            private_key: String,
-            wallet_key: String,
-            votes: Vec<u64>
-        ) -> String {
-    let keys = KeyStore::init(public_key, private_key, wallet_key).expect("failed to make keys");
-    let ballot = keys.contract(Address::from_str(&contract_address).expect("no string")).expect("problem");
-    let mut converted: Vec<Bytes> = vec![];
-    let public_key_bytes = ballot.get_public_key().call().await.expect("no public key");
-    let public_key = PublicKey::from_bytes(&public_key_bytes).expect("weird public key");
-    for vote in votes {
-        let encrypt: Ciphertext = RUNTIME.encrypt(Unsigned64::from(vote), &public_key).expect("Could not encrypt");
-        converted.push(encrypt.as_bytes().expect("Could not encode into bytes"));
-    }
-
-    let result = ballot.vote(converted).send().await.expect("some ").await.expect(" problem ");
-    serde_json::to_string(&result).expect("pls")
+pub async fn change_vote(
+    contract_address: String,
+    public_key: String,
+    private_key: String,
+    wallet_key: String,
+    network: NetworkConfig,
+    votes: Vec<u64>,
+    rule: VotingRule,
+) -> Result<TxOutcome, BallotError> {
+    let client = BallotClient::new(public_key, private_key, wallet_key, network, contract_address)?;
+    client.change_vote(votes, rule).await
 }
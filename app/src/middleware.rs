@@ -0,0 +1,309 @@
+//! Resilient transaction submission.
+//!
+//! Parasol's RPC node drops the odd request, and a transaction priced for a quiet block can sit
+//! in the mempool for a long time. Every client from [`NetworkConfig::client`] is therefore
+//! built in layers around the wallet's signer:
+//!
+//! - its transport retries requests that time out, can't connect or are rate limited, backing
+//!   off between attempts;
+//! - [`ResilientMiddleware`] hands out nonces itself and sends one transaction at a time, so
+//!   transactions sent together reach the node in nonce order;
+//! - it then waits for each transaction to be mined, resending it with the same nonce and a
+//!   higher gas price whenever it sits unmined for too long, until it has enough confirmations
+//!   or the timeout runs out.
+//!
+//! So sending a transaction through the client only returns once it is mined and confirmed. How
+//! patient to be is set per network by its [`TxPolicy`].
+//!
+//! [`NetworkConfig::client`]: crate::NetworkConfig::client
+
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use ethers::{
+    middleware::{signer::SignerMiddlewareError, SignerMiddleware},
+    providers::{
+        Http, HttpClientError, HttpRateLimitRetryPolicy, Middleware, MiddlewareError,
+        PendingTransaction, Provider, RetryClient, RetryClientBuilder, RetryPolicy,
+    },
+    signers::{LocalWallet, Signer},
+    types::{
+        transaction::eip2718::TypedTransaction, BlockId, BlockNumber, TransactionReceipt, TxHash,
+        U256,
+    },
+};
+use serde::{Deserialize, Serialize};
+use tokio::{sync::Mutex, time};
+
+use crate::BallotError;
+
+/// How often to check on a transaction that's been sent.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The client every command and export talks to the chain through.
+pub type BallotMiddleware = ResilientMiddleware<Provider<RetryClient<Http>>, LocalWallet>;
+
+/// How clients send transactions and wait for them.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, uniffi::Record)]
+#[serde(default)]
+pub struct TxPolicy {
+    /// Times to retry a request that times out, can't connect or is rate limited
+    pub retries: u32,
+    /// Wait before retrying a request, in milliseconds
+    pub retry_backoff_ms: u64,
+    /// Longest to wait for the node to answer a request, in seconds
+    pub request_timeout_secs: u64,
+    /// Blocks a transaction needs, counting the one it's in, before it counts as sent
+    pub confirmations: u64,
+    /// Longest to wait for a transaction to be mined and confirmed, in seconds
+    pub timeout_secs: u64,
+    /// How long a transaction can go unmined before it's resent with a higher gas price, in
+    /// seconds
+    pub escalate_after_secs: u64,
+    /// How much to raise the gas price by on each resend, in percent. Nodes only take a
+    /// replacement that pays at least 10% more.
+    pub gas_bump_percent: u64,
+    /// Most times to resend a transaction with a higher gas price
+    pub max_escalations: u32,
+}
+
+impl Default for TxPolicy {
+    fn default() -> Self {
+        Self {
+            retries: 5,
+            retry_backoff_ms: 1000,
+            request_timeout_secs: 30,
+            confirmations: 1,
+            timeout_secs: 300,
+            escalate_after_secs: 60,
+            gas_bump_percent: 20,
+            max_escalations: 3,
+        }
+    }
+}
+
+/// Retries what [`HttpRateLimitRetryPolicy`] does, plus requests that time out or can't
+/// connect, so those back off too rather than being retried straight away.
+#[derive(Debug, Default)]
+struct TransportRetryPolicy(HttpRateLimitRetryPolicy);
+
+impl RetryPolicy<HttpClientError> for TransportRetryPolicy {
+    fn should_retry(&self, error: &HttpClientError) -> bool {
+        match error {
+            HttpClientError::ReqwestError(e) if e.is_timeout() || e.is_connect() => true,
+            error => self.0.should_retry(error),
+        }
+    }
+
+    fn backoff_hint(&self, error: &HttpClientError) -> Option<Duration> {
+        self.0.backoff_hint(error)
+    }
+}
+
+/// A provider for `rpc_url` that retries failed requests as `policy` says.
+pub(crate) fn provider(
+    rpc_url: &str,
+    policy: &TxPolicy,
+) -> Result<Provider<RetryClient<Http>>, BallotError> {
+    let url = reqwest::Url::parse(rpc_url)
+        .map_err(|e| BallotError::config(format!("invalid rpc url {rpc_url:?}: {e}")))?;
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(policy.request_timeout_secs))
+        .build()
+        .map_err(BallotError::config)?;
+    let transport = RetryClientBuilder::default()
+        .rate_limit_retries(policy.retries)
+        .timeout_retries(policy.retries)
+        .initial_backoff(Duration::from_millis(policy.retry_backoff_ms))
+        .build(Http::new_with_client(url, client), Box::<TransportRetryPolicy>::default());
+    Ok(Provider::new(transport).interval(POLL_INTERVAL))
+}
+
+/// Errors from [`ResilientMiddleware`].
+#[derive(Debug, thiserror::Error)]
+pub enum ResilientError<M: Middleware, S: Signer> {
+    #[error(transparent)]
+    Middleware(SignerMiddlewareError<M, S>),
+
+    #[error("transaction {tx_hash:?} was not confirmed within {seconds} seconds")]
+    Timeout { tx_hash: TxHash, seconds: u64 },
+}
+
+impl<M: Middleware, S: Signer> MiddlewareError for ResilientError<M, S> {
+    type Inner = SignerMiddlewareError<M, S>;
+
+    fn from_err(src: Self::Inner) -> Self {
+        Self::Middleware(src)
+    }
+
+    fn as_inner(&self) -> Option<&Self::Inner> {
+        match self {
+            Self::Middleware(e) => Some(e),
+            Self::Timeout { .. } => None,
+        }
+    }
+}
+
+/// Sends transactions one at a time with locally managed nonces, and waits for each to be
+/// confirmed, raising its gas price while it's stuck.
+///
+/// Unlike ethers' `NonceManagerMiddleware`, a transaction that fails before it's sent (for
+/// example because it would revert) doesn't use up its nonce, and a send that fails makes the
+/// next one ask the node for the nonce again.
+#[derive(Debug)]
+pub struct ResilientMiddleware<M, S> {
+    inner: SignerMiddleware<M, S>,
+    policy: TxPolicy,
+    /// The next nonce to use, if known. Locked for as long as a transaction is being filled in
+    /// and sent.
+    next_nonce: Mutex<Option<U256>>,
+}
+
+impl<M: Middleware, S: Signer> ResilientMiddleware<M, S> {
+    pub fn new(inner: SignerMiddleware<M, S>, policy: TxPolicy) -> Self {
+        Self { inner, policy, next_nonce: Mutex::default() }
+    }
+
+    pub fn policy(&self) -> &TxPolicy {
+        &self.policy
+    }
+
+    /// Sign `tx` and hand it to the node, returning its hash.
+    ///
+    /// If the node answers with an error but has the transaction anyway, say because an
+    /// earlier attempt got through before the request was retried, the send still counts.
+    async fn broadcast(&self, tx: &TypedTransaction) -> Result<TxHash, ResilientError<M, S>> {
+        let signature = self
+            .inner
+            .signer()
+            .sign_transaction(tx)
+            .await
+            .map_err(|e| ResilientError::Middleware(SignerMiddlewareError::SignerError(e)))?;
+        let tx_hash = tx.hash(&signature);
+        match self.inner.inner().send_raw_transaction(tx.rlp_signed(&signature)).await {
+            Ok(_) => Ok(tx_hash),
+            Err(_) if matches!(self.get_transaction(tx_hash).await, Ok(Some(_))) => Ok(tx_hash),
+            Err(e) => Err(ResilientError::Middleware(SignerMiddlewareError::MiddlewareError(e))),
+        }
+    }
+
+    /// Fill in `tx` with the next nonce and send it.
+    async fn send(
+        &self,
+        tx: &mut TypedTransaction,
+        block: Option<BlockId>,
+    ) -> Result<TxHash, ResilientError<M, S>> {
+        let mut next_nonce = self.next_nonce.lock().await;
+        let nonce = match *next_nonce {
+            Some(nonce) => nonce,
+            None => self
+                .inner
+                .get_transaction_count(self.inner.address(), Some(BlockNumber::Pending.into()))
+                .await
+                .map_err(ResilientError::Middleware)?,
+        };
+        tx.set_nonce(nonce);
+        self.inner.fill_transaction(tx, block).await.map_err(ResilientError::Middleware)?;
+
+        let sent = self.broadcast(tx).await;
+        *next_nonce = sent.is_ok().then(|| nonce + 1);
+        sent
+    }
+
+    /// The receipt of whichever of `hashes` was mined, if one was.
+    async fn mined(&self, hashes: &[TxHash]) -> Option<TransactionReceipt> {
+        for &hash in hashes {
+            // The transport has already retried; a failure here only means checking again later
+            if let Ok(Some(receipt)) = self.get_transaction_receipt(hash).await {
+                return Some(receipt);
+            }
+        }
+        None
+    }
+
+    async fn is_confirmed(&self, receipt: &TransactionReceipt) -> bool {
+        let (Some(mined), Ok(latest)) = (receipt.block_number, self.get_block_number().await)
+        else {
+            return false;
+        };
+        latest + 1 >= mined + self.policy.confirmations
+    }
+
+    /// Wait for `tx`, sent as `tx_hash`, to be mined and confirmed, resending it at a higher gas
+    /// price while it's stuck.
+    async fn confirm(
+        &self,
+        mut tx: TypedTransaction,
+        tx_hash: TxHash,
+    ) -> Result<TransactionReceipt, ResilientError<M, S>> {
+        let policy = &self.policy;
+        let started = Instant::now();
+        let mut sent = started;
+        let mut escalations = 0;
+        let mut hashes = vec![tx_hash];
+        loop {
+            // Checked every time, since a reorg can take a mined transaction back out
+            match self.mined(&hashes).await {
+                Some(receipt) if self.is_confirmed(&receipt).await => return Ok(receipt),
+                Some(_) => {}
+                None if sent.elapsed().as_secs() >= policy.escalate_after_secs
+                    && escalations < policy.max_escalations =>
+                {
+                    bump_gas_price(&mut tx, policy.gas_bump_percent);
+                    // A rejected replacement usually means an earlier send is being mined
+                    if let Ok(hash) = self.broadcast(&tx).await {
+                        hashes.push(hash);
+                    }
+                    escalations += 1;
+                    sent = Instant::now();
+                }
+                None => {}
+            }
+
+            if started.elapsed().as_secs() >= policy.timeout_secs {
+                let tx_hash = hashes[hashes.len() - 1];
+                return Err(ResilientError::Timeout { tx_hash, seconds: policy.timeout_secs });
+            }
+            time::sleep(POLL_INTERVAL).await;
+        }
+    }
+}
+
+/// Raise what `tx` pays for gas by `percent`.
+fn bump_gas_price(tx: &mut TypedTransaction, percent: u64) {
+    let bump = |price: U256| price * (100 + percent) / 100;
+    match tx {
+        TypedTransaction::Eip1559(inner) => {
+            inner.max_fee_per_gas = inner.max_fee_per_gas.map(bump);
+            inner.max_priority_fee_per_gas = inner.max_priority_fee_per_gas.map(bump);
+        }
+        tx => {
+            if let Some(price) = tx.gas_price() {
+                tx.set_gas_price(bump(price));
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl<M: Middleware, S: Signer> Middleware for ResilientMiddleware<M, S> {
+    type Error = ResilientError<M, S>;
+    type Provider = M::Provider;
+    type Inner = SignerMiddleware<M, S>;
+
+    fn inner(&self) -> &Self::Inner {
+        &self.inner
+    }
+
+    /// Send `tx`, and wait for it to be confirmed. The returned transaction is already mined.
+    async fn send_transaction<T: Into<TypedTransaction> + Send + Sync>(
+        &self,
+        tx: T,
+        block: Option<BlockId>,
+    ) -> Result<PendingTransaction<'_, Self::Provider>, Self::Error> {
+        let mut tx = tx.into();
+        let tx_hash = self.send(&mut tx, block).await?;
+        let receipt = self.confirm(tx, tx_hash).await?;
+        Ok(PendingTransaction::new(receipt.transaction_hash, self.provider()))
+    }
+}
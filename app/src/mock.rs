@@ -0,0 +1,800 @@
+//! An in-process stand-in for a chain running the ballot contract.
+//!
+//! [`MockChain`] plays the part of the FHE network: it holds the network keypair and evaluates
+//! the same homomorphic additions and subtractions the precompiles would, with the local
+//! `sunscreen` runtime. [`MockBallot`] is a [`BallotBackend`] over one ballot on it, so the CLI,
+//! the app and tests can run a whole election with no node at all.
+//!
+//! The chain can be serialized with [`MockChain::to_bytes`], which is how the CLI keeps it
+//! between invocations. It holds the network private key in the clear; it is a test fixture,
+//! not a vault.
+
+use std::{
+    collections::BTreeMap,
+    str::FromStr,
+    sync::{Arc, Mutex, MutexGuard, OnceLock},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use async_trait::async_trait;
+use bindings::ballot::Proposal;
+use ethers::{
+    types::{Address, Bytes, H256},
+    utils::keccak256,
+};
+use futures::{
+    stream::{self, BoxStream},
+    StreamExt,
+};
+use serde::{Deserialize, Serialize};
+use sunscreen::{
+    fhe_program,
+    types::{
+        bfv::{Unsigned256, Unsigned64},
+        Cipher,
+    },
+    Ciphertext, CompiledFheProgram, Compiler, PrivateKey, PublicKey,
+};
+use sunscreen_web3::{
+    testnet::parasol::{generate_keys, RUNTIME},
+    AsBytes,
+};
+use tokio::sync::broadcast;
+
+use crate::{
+    packing::from_unsigned, unpack, verify_proof, BallotBackend, BallotError, BallotEvent,
+    EncUint256, EncUint64, EncVotes, Packing, Phase, SignedVote, TxOutcome, TxStatus,
+};
+
+#[fhe_program(scheme = "bfv")]
+fn add(a: Cipher<Unsigned64>, b: Cipher<Unsigned64>) -> Cipher<Unsigned64> {
+    a + b
+}
+
+#[fhe_program(scheme = "bfv")]
+fn subtract(a: Cipher<Unsigned64>, b: Cipher<Unsigned64>) -> Cipher<Unsigned64> {
+    a - b
+}
+
+#[fhe_program(scheme = "bfv")]
+fn add_packed(a: Cipher<Unsigned256>, b: Cipher<Unsigned256>) -> Cipher<Unsigned256> {
+    a + b
+}
+
+#[fhe_program(scheme = "bfv")]
+fn subtract_packed(a: Cipher<Unsigned256>, b: Cipher<Unsigned256>) -> Cipher<Unsigned256> {
+    a - b
+}
+
+/// The mock's stand-ins for `addUint64EncEnc` and `subtractUint64EncEnc`, and for their `Uint256`
+/// versions on packed ballots.
+struct Programs {
+    add: CompiledFheProgram,
+    subtract: CompiledFheProgram,
+    add_packed: CompiledFheProgram,
+    subtract_packed: CompiledFheProgram,
+}
+
+static PROGRAMS: OnceLock<Programs> = OnceLock::new();
+
+/// Compile the FHE programs for the runtime's parameters, once per process.
+fn programs() -> Result<&'static Programs, BallotError> {
+    if let Some(programs) = PROGRAMS.get() {
+        return Ok(programs);
+    }
+
+    let app = Compiler::new()
+        .fhe_program(add)
+        .fhe_program(subtract)
+        .fhe_program(add_packed)
+        .fhe_program(subtract_packed)
+        .with_params(RUNTIME.params())
+        .compile()
+        .map_err(BallotError::fhe)?;
+    let program = |name| {
+        app.get_fhe_program(name)
+            .cloned()
+            .ok_or_else(|| BallotError::fhe(format!("{name} was not compiled")))
+    };
+    let programs = Programs {
+        add: program("add")?,
+        subtract: program("subtract")?,
+        add_packed: program("add_packed")?,
+        subtract_packed: program("subtract_packed")?,
+    };
+    Ok(PROGRAMS.get_or_init(|| programs))
+}
+
+/// The mock's chain id, which is Anvil's.
+const CHAIN_ID: u64 = 31337;
+
+/// The mock's `block.timestamp`: the wall clock, as a Unix timestamp.
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |since| since.as_secs())
+}
+
+#[derive(Serialize, Deserialize)]
+struct MockProposal {
+    name: String,
+    contents: String,
+    /// `None` on a packed ballot, which counts every proposal in its packed count
+    vote_count: Option<Ciphertext>,
+}
+
+impl MockProposal {
+    /// Decrypt the tally with the network key, as the precompiles' `decryptUint64` would.
+    fn decrypt(&self, private_key: &PrivateKey) -> Result<u64, BallotError> {
+        let vote_count = self
+            .vote_count
+            .as_ref()
+            .ok_or_else(|| BallotError::fhe("the proposal is counted in the packed count"))?;
+        let tally = EncUint64::from_ciphertext(vote_count)?;
+        let tally = crypto_bigint::Uint::<1>::from(tally.decrypt(private_key)?);
+        Ok(tally.as_words()[0])
+    }
+}
+
+/// Storage of one deployed ballot, laid out like the contract's.
+#[derive(Default, Serialize, Deserialize)]
+struct MockContract {
+    proposals: Vec<MockProposal>,
+    voters: BTreeMap<Address, Vec<Ciphertext>>,
+    nonces: BTreeMap<Address, u64>,
+    organiser: Address,
+    voter_root: H256,
+    /// 0 unless the ballot is packed
+    slot_bits: u8,
+    packed_count: Option<Ciphertext>,
+    phase: Phase,
+    voting_ends: u64,
+    results: Vec<u64>,
+    events: Vec<BallotEvent>,
+}
+
+impl MockContract {
+    /// The packed count decrypted with the network key, as `decryptUint256` would, if the ballot
+    /// is packed.
+    fn decrypt_packed(&self, private_key: &PrivateKey) -> Result<Option<Unsigned256>, BallotError> {
+        self.packed_count
+            .as_ref()
+            .map(|packed| EncUint256::from_ciphertext(packed)?.decrypt(private_key))
+            .transpose()
+    }
+
+    /// Every proposal's tally, decrypted with the network key, as `close` does.
+    fn decrypt_tallies(&self, private_key: &PrivateKey) -> Result<Vec<u64>, BallotError> {
+        match self.decrypt_packed(private_key)? {
+            Some(packed) => Ok(unpack(from_unsigned(packed), self.slot_bits, self.proposals.len())),
+            None => self.proposals.iter().map(|proposal| proposal.decrypt(private_key)).collect(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct ChainState {
+    /// Bumped by every transaction, so events can be ordered and filtered like real ones
+    block_number: u64,
+    public_key: PublicKey,
+    private_key: PrivateKey,
+    contracts: BTreeMap<Address, MockContract>,
+}
+
+impl ChainState {
+    fn contract(&mut self, address: Address) -> Result<&mut MockContract, BallotError> {
+        self.contracts
+            .get_mut(&address)
+            .ok_or_else(|| BallotError::address(format!("no ballot deployed at {address:?}")))
+    }
+
+    fn run(
+        &self,
+        program: &CompiledFheProgram,
+        a: &Ciphertext,
+        b: &Ciphertext,
+    ) -> Result<Ciphertext, BallotError> {
+        RUNTIME
+            .run(program, vec![a.clone(), b.clone()], &self.public_key)
+            .map_err(BallotError::fhe)?
+            .pop()
+            .ok_or_else(|| BallotError::fhe("program produced no output"))
+    }
+}
+
+/// An in-memory chain that ballots can be deployed to.
+#[derive(uniffi::Object)]
+pub struct MockChain {
+    state: Mutex<ChainState>,
+    events: broadcast::Sender<(Address, BallotEvent)>,
+}
+
+impl MockChain {
+    fn with_state(state: ChainState) -> Arc<Self> {
+        let (events, _) = broadcast::channel(64);
+        Arc::new(Self { state: Mutex::new(state), events })
+    }
+
+    fn state(&self) -> Result<MutexGuard<'_, ChainState>, BallotError> {
+        self.state.lock().map_err(BallotError::runtime)
+    }
+
+    /// A handle to the ballot at `address`, sending transactions as `sender`.
+    pub fn ballot(self: &Arc<Self>, address: Address, sender: Address) -> MockBallot {
+        MockBallot { chain: Arc::clone(self), address, sender }
+    }
+
+    /// Mine a block holding a transaction to the ballot at `address`, which emitted `event`.
+    fn emit(
+        &self,
+        state: &mut ChainState,
+        address: Address,
+        event: impl FnOnce(u64) -> BallotEvent,
+    ) -> TxOutcome {
+        self.emit_all(state, address, |block_number| vec![event(block_number)])
+    }
+
+    /// Like [`MockChain::emit`], for a transaction that emitted any number of events.
+    fn emit_all(
+        &self,
+        state: &mut ChainState,
+        address: Address,
+        events: impl FnOnce(u64) -> Vec<BallotEvent>,
+    ) -> TxOutcome {
+        state.block_number += 1;
+        let block_number = state.block_number;
+        for event in events(block_number) {
+            if let Some(contract) = state.contracts.get_mut(&address) {
+                contract.events.push(event.clone());
+            }
+            // Nobody listening is fine
+            let _ = self.events.send((address, event));
+        }
+
+        mined(address, block_number)
+    }
+}
+
+/// A successful mock transaction to `address`, in block `block_number`.
+///
+/// The mock charges no gas, and its transaction hashes are made up from the block.
+fn mined(address: Address, block_number: u64) -> TxOutcome {
+    let tx_hash = H256::from(keccak256([address.as_bytes(), &block_number.to_be_bytes()].concat()));
+    TxOutcome {
+        tx_hash: format!("{tx_hash:?}"),
+        block_number,
+        gas_used: 0,
+        effective_gas_price: None,
+        status: TxStatus::Success,
+        revert_reason: None,
+        contract_address: None,
+    }
+}
+
+#[uniffi::export]
+impl MockChain {
+    /// Start an empty chain with a freshly generated network key.
+    #[uniffi::constructor]
+    pub fn new() -> Result<Arc<Self>, BallotError> {
+        let (public_key, private_key) = generate_keys().map_err(BallotError::key)?;
+        Ok(Self::with_state(ChainState {
+            block_number: 0,
+            public_key,
+            private_key,
+            contracts: BTreeMap::new(),
+        }))
+    }
+
+    /// Restore a chain saved with [`MockChain::to_bytes`].
+    #[uniffi::constructor]
+    pub fn from_bytes(bytes: Vec<u8>) -> Result<Arc<Self>, BallotError> {
+        let state = serde_json::from_slice(&bytes).map_err(BallotError::serialization)?;
+        Ok(Self::with_state(state))
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>, BallotError> {
+        serde_json::to_vec(&*self.state()?).map_err(BallotError::serialization)
+    }
+
+    /// Deploy a new, empty ballot with `organiser` as its deployer. Its address is the outcome's
+    /// `contract_address`.
+    ///
+    /// `voter_root` is the root of an [`Allowlist`](crate::Allowlist) to restrict voting to;
+    /// anyone can vote without one. With `packing`, the ballot counts every proposal in one
+    /// ciphertext; see [`Packing`].
+    pub fn deploy(
+        &self,
+        organiser: String,
+        voter_root: Option<String>,
+        packing: Option<Packing>,
+    ) -> Result<TxOutcome, BallotError> {
+        let organiser = Address::from_str(&organiser).map_err(BallotError::address)?;
+        let voter_root = voter_root
+            .map(|root| H256::from_str(&root).map_err(BallotError::allowlist))
+            .transpose()?
+            .unwrap_or_default();
+        let slot_bits = packing.map(|packing| packing.slot_bits()).transpose()?.unwrap_or_default();
+        let mut state = self.state()?;
+        let packed_count = match slot_bits {
+            0 => None,
+            _ => Some(EncUint256::encrypt(0, &state.public_key)?.to_ciphertext()?),
+        };
+        state.block_number += 1;
+        let seed = [state.block_number.to_be_bytes(), (state.contracts.len() as u64).to_be_bytes()];
+        let address = Address::from_slice(&keccak256(seed.concat())[12..]);
+        let contract = MockContract {
+            organiser,
+            voter_root,
+            slot_bits,
+            packed_count,
+            ..MockContract::default()
+        };
+        state.contracts.insert(address, contract);
+        Ok(TxOutcome {
+            contract_address: Some(format!("{address:x}")),
+            ..mined(address, state.block_number)
+        })
+    }
+}
+
+/// One ballot on a [`MockChain`].
+#[derive(Clone)]
+pub struct MockBallot {
+    chain: Arc<MockChain>,
+    address: Address,
+    sender: Address,
+}
+
+impl MockBallot {
+    /// Replace `voter`'s previous votes (if any) with `votes`, like `Ballot._vote`.
+    fn cast(
+        &self,
+        voter: Address,
+        votes: Vec<Bytes>,
+        proof: &[H256],
+        require_voted: bool,
+    ) -> Result<TxOutcome, BallotError> {
+        let programs = programs()?;
+        let mut state = self.chain.state()?;
+        let votes = votes
+            .iter()
+            .map(|vote| Ciphertext::from_bytes(vote))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| BallotError::revert(format!("invalid ciphertext: {e}")))?;
+
+        // Work on a copy so a failed evaluation leaves the tallies untouched, like a revert. A
+        // packed ballot has the one tally, its packed count.
+        let contract = state.contract(self.address)?;
+        let previous = contract.voters.get(&voter).cloned();
+        let packed = contract.packed_count.is_some();
+        let mut tallies: Vec<Ciphertext> = match &contract.packed_count {
+            Some(packed_count) => vec![packed_count.clone()],
+            None => contract
+                .proposals
+                .iter()
+                .filter_map(|proposal| proposal.vote_count.clone())
+                .collect(),
+        };
+        if require_voted && previous.is_none() {
+            return Err(BallotError::revert("No vote to change"));
+        }
+        if contract.phase != Phase::Voting || now() >= contract.voting_ends {
+            return Err(BallotError::revert("Voting is not open"));
+        }
+        if !verify_proof(contract.voter_root, voter, proof) {
+            return Err(BallotError::revert("Not on the voter allowlist"));
+        }
+        if packed && votes.len() != 1 {
+            return Err(BallotError::revert("Packed ballots take one packed vote"));
+        }
+        if votes.len() != tallies.len() {
+            return Err(BallotError::revert("You need to give exactly as many votes as proposals"));
+        }
+
+        let (add_program, subtract_program) = if packed {
+            (&programs.add_packed, &programs.subtract_packed)
+        } else {
+            (&programs.add, &programs.subtract)
+        };
+        // Proposals added since the previous vote have nothing to take back
+        if let Some(previous) = &previous {
+            for (tally, vote) in tallies.iter_mut().zip(previous) {
+                *tally = state.run(subtract_program, tally, vote)?;
+            }
+        }
+        for (tally, vote) in tallies.iter_mut().zip(&votes) {
+            *tally = state.run(add_program, tally, vote)?;
+        }
+
+        let contract = state.contract(self.address)?;
+        if packed {
+            contract.packed_count = tallies.pop();
+        } else {
+            for (proposal, tally) in contract.proposals.iter_mut().zip(tallies) {
+                proposal.vote_count = Some(tally);
+            }
+        }
+        contract.voters.insert(voter, votes);
+
+        let (voter, replaced) = (format!("{voter:?}"), previous.is_some());
+        Ok(self.chain.emit(&mut state, self.address, |block_number| BallotEvent::VoteCast {
+            voter,
+            replaced,
+            block_number,
+        }))
+    }
+}
+
+#[async_trait]
+impl BallotBackend for MockBallot {
+    fn address(&self) -> Address {
+        self.address
+    }
+
+    async fn chain_id(&self) -> Result<u64, BallotError> {
+        Ok(CHAIN_ID)
+    }
+
+    async fn get_public_key(&self) -> Result<Bytes, BallotError> {
+        self.chain.state()?.public_key.as_bytes().map_err(BallotError::serialization)
+    }
+
+    async fn get_proposals(&self) -> Result<Vec<Proposal>, BallotError> {
+        let mut state = self.chain.state()?;
+        state
+            .contract(self.address)?
+            .proposals
+            .iter()
+            .map(|proposal| {
+                Ok(Proposal {
+                    name: proposal.name.clone(),
+                    contents: proposal.contents.clone(),
+                    vote_count: proposal
+                        .vote_count
+                        .as_ref()
+                        .map(AsBytes::as_bytes)
+                        .transpose()
+                        .map_err(BallotError::serialization)?
+                        .unwrap_or_default(),
+                })
+            })
+            .collect()
+    }
+
+    async fn has_voted(&self, voter: Address) -> Result<bool, BallotError> {
+        Ok(self.chain.state()?.contract(self.address)?.voters.contains_key(&voter))
+    }
+
+    async fn nonce(&self, voter: Address) -> Result<u64, BallotError> {
+        let mut state = self.chain.state()?;
+        Ok(state.contract(self.address)?.nonces.get(&voter).copied().unwrap_or_default())
+    }
+
+    async fn phase(&self) -> Result<Phase, BallotError> {
+        Ok(self.chain.state()?.contract(self.address)?.phase)
+    }
+
+    async fn voter_root(&self) -> Result<H256, BallotError> {
+        Ok(self.chain.state()?.contract(self.address)?.voter_root)
+    }
+
+    async fn voting_ends(&self) -> Result<u64, BallotError> {
+        Ok(self.chain.state()?.contract(self.address)?.voting_ends)
+    }
+
+    async fn slot_bits(&self) -> Result<u8, BallotError> {
+        Ok(self.chain.state()?.contract(self.address)?.slot_bits)
+    }
+
+    async fn packed_count(&self) -> Result<Bytes, BallotError> {
+        let mut state = self.chain.state()?;
+        let packed_count =
+            state.contract(self.address)?.packed_count.as_ref().map(AsBytes::as_bytes);
+        Ok(packed_count.transpose().map_err(BallotError::serialization)?.unwrap_or_default())
+    }
+
+    async fn get_results(&self) -> Result<Vec<u64>, BallotError> {
+        let mut state = self.chain.state()?;
+        let contract = state.contract(self.address)?;
+        if contract.phase != Phase::Closed {
+            return Err(BallotError::revert("Voting has not closed"));
+        }
+        Ok(contract.results.clone())
+    }
+
+    async fn get_proposal_tallys(
+        &self,
+        reenc_public_key: Bytes,
+    ) -> Result<Vec<EncUint256>, BallotError> {
+        let reenc_public_key =
+            PublicKey::from_bytes(&reenc_public_key).map_err(BallotError::key)?;
+        let mut state = self.chain.state()?;
+        let private_key = state.private_key.clone();
+
+        // Re-encryption needs the network to decrypt, which is exactly what the mock can do
+        let contract = state.contract(self.address)?;
+        if let Some(packed) = contract.decrypt_packed(&private_key)? {
+            return Ok(vec![EncUint256::encrypt(packed, &reenc_public_key)?]);
+        }
+        contract
+            .proposals
+            .iter()
+            .map(|proposal| EncUint256::encrypt(proposal.decrypt(&private_key)?, &reenc_public_key))
+            .collect()
+    }
+
+    async fn add_proposal(&self, name: String, contents: String) -> Result<TxOutcome, BallotError> {
+        self.add_proposals(vec![(name, contents)]).await
+    }
+
+    async fn add_proposals(
+        &self,
+        proposals: Vec<(String, String)>,
+    ) -> Result<TxOutcome, BallotError> {
+        let mut state = self.chain.state()?;
+        let slot_bits = usize::from(state.contract(self.address)?.slot_bits);
+        // A packed ballot's proposals have no count of their own
+        let zero = match slot_bits {
+            0 => Some(EncUint64::encrypt(0, &state.public_key)?.to_ciphertext()?),
+            _ => None,
+        };
+        let contract = state.contract(self.address)?;
+        if self.sender != contract.organiser {
+            return Err(BallotError::revert("Only the organiser can add proposals"));
+        }
+        if contract.phase != Phase::Setup {
+            return Err(BallotError::revert("Voting has already opened"));
+        }
+        // All or nothing, like a revert part way through `addProposals`
+        if (contract.proposals.len() + proposals.len()) * slot_bits > 256 {
+            return Err(BallotError::revert("No room in the packed count for another proposal"));
+        }
+        let first = contract.proposals.len() as u64;
+        let names: Vec<_> = proposals.iter().map(|(name, _)| name.clone()).collect();
+        contract.proposals.extend(proposals.into_iter().map(|(name, contents)| MockProposal {
+            name,
+            contents,
+            vote_count: zero.clone(),
+        }));
+
+        Ok(self.chain.emit_all(&mut state, self.address, |block_number| {
+            (first..)
+                .zip(names)
+                .map(|(index, name)| BallotEvent::ProposalAdded { index, name, block_number })
+                .collect()
+        }))
+    }
+
+    async fn vote(&self, votes: EncVotes, proof: Vec<H256>) -> Result<TxOutcome, BallotError> {
+        self.cast(self.sender, votes.into_bytes(), &proof, false)
+    }
+
+    async fn change_vote(
+        &self,
+        votes: EncVotes,
+        proof: Vec<H256>,
+    ) -> Result<TxOutcome, BallotError> {
+        self.cast(self.sender, votes.into_bytes(), &proof, true)
+    }
+
+    async fn vote_by_sig(&self, vote: SignedVote) -> Result<TxOutcome, BallotError> {
+        if now() > vote.deadline {
+            return Err(BallotError::revert("Signature expired"));
+        }
+        // A stale nonce changes the digest on chain, so it shows up as a bad signature
+        if vote.nonce != self.nonce(vote.voter).await? {
+            return Err(BallotError::revert("Invalid signature"));
+        }
+        vote.verify(CHAIN_ID)?;
+
+        let outcome = self.cast(vote.voter, vote.votes, &vote.proof, false)?;
+        let mut state = self.chain.state()?;
+        *state.contract(self.address)?.nonces.entry(vote.voter).or_default() += 1;
+        Ok(outcome)
+    }
+
+    async fn open(&self, voting_ends: u64) -> Result<TxOutcome, BallotError> {
+        let mut state = self.chain.state()?;
+        let contract = state.contract(self.address)?;
+        if self.sender != contract.organiser {
+            return Err(BallotError::revert("Only the organiser can open voting"));
+        }
+        if contract.phase != Phase::Setup {
+            return Err(BallotError::revert("Voting has already opened"));
+        }
+        if contract.proposals.is_empty() {
+            return Err(BallotError::revert("There are no proposals to vote on"));
+        }
+        if voting_ends <= now() {
+            return Err(BallotError::revert("Voting must end in the future"));
+        }
+        contract.phase = Phase::Voting;
+        contract.voting_ends = voting_ends;
+
+        Ok(self.chain.emit(&mut state, self.address, |block_number| BallotEvent::VotingOpened {
+            voting_ends,
+            block_number,
+        }))
+    }
+
+    async fn close(&self) -> Result<TxOutcome, BallotError> {
+        let mut state = self.chain.state()?;
+        let private_key = state.private_key.clone();
+        let contract = state.contract(self.address)?;
+        if contract.phase != Phase::Voting {
+            return Err(BallotError::revert("Voting is not open"));
+        }
+        if self.sender != contract.organiser && now() < contract.voting_ends {
+            return Err(BallotError::revert("Only the organiser can close voting before it ends"));
+        }
+        let results = contract.decrypt_tallies(&private_key)?;
+        contract.phase = Phase::Closed;
+        contract.results = results.clone();
+
+        Ok(self.chain.emit(&mut state, self.address, |block_number| BallotEvent::VotingClosed {
+            results,
+            block_number,
+        }))
+    }
+
+    async fn events(
+        &self,
+        from_block: Option<u64>,
+    ) -> Result<BoxStream<'_, Result<BallotEvent, BallotError>>, BallotError> {
+        // Subscribe before reading the history, as with the on-chain filter
+        let receiver = self.chain.events.subscribe();
+        let mut state = self.chain.state()?;
+        // Without history, events mined between subscribing and locking the state are new
+        let head = from_block.map_or(0, |_| state.block_number);
+        let history: Vec<_> = match from_block {
+            Some(from_block) => state
+                .contract(self.address)?
+                .events
+                .iter()
+                .filter(|event| event.block_number() >= from_block)
+                .cloned()
+                .map(Ok)
+                .collect(),
+            None => Vec::new(),
+        };
+        drop(state);
+
+        let address = self.address;
+        let live = stream::unfold(receiver, move |mut receiver| async move {
+            loop {
+                match receiver.recv().await {
+                    Ok((from, event)) if from == address && event.block_number() > head => {
+                        return Some((Ok(event), receiver))
+                    }
+                    Ok(_) => continue,
+                    Err(broadcast::error::RecvError::Lagged(missed)) => {
+                        let e =
+                            BallotError::runtime(format!("fell behind and missed {missed} events"));
+                        return Some((Err(e), receiver));
+                    }
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                }
+            }
+        });
+        Ok(stream::iter(history).chain(live).boxed())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ethers::signers::{LocalWallet, Signer};
+
+    use super::*;
+    use crate::{sign_vote, Allowlist};
+
+    const ORGANISER: Address = Address::repeat_byte(0x01);
+
+    /// Deploy a ballot with two proposals and open it for an hour, returning the organiser's
+    /// handle to it.
+    async fn open_ballot(
+        chain: &Arc<MockChain>,
+        voter_root: Option<H256>,
+        packing: Option<Packing>,
+    ) -> MockBallot {
+        let deployed = chain
+            .deploy(format!("{ORGANISER:?}"), voter_root.map(|root| format!("{root:?}")), packing)
+            .unwrap();
+        let address = Address::from_str(&deployed.contract_address.unwrap()).unwrap();
+        let ballot = chain.ballot(address, ORGANISER);
+        ballot.add_proposal("a".into(), "first".into()).await.unwrap();
+        ballot.add_proposal("b".into(), "second".into()).await.unwrap();
+        ballot.open(now() + 3600).await.unwrap();
+        ballot
+    }
+
+    async fn encrypt(ballot: &MockBallot, votes: &[u64]) -> EncVotes {
+        let public_key = PublicKey::from_bytes(&ballot.get_public_key().await.unwrap()).unwrap();
+        EncVotes::encrypt(votes, ballot.slot_bits().await.unwrap(), &public_key).unwrap()
+    }
+
+    async fn election(packing: Option<Packing>) {
+        let chain = MockChain::new().unwrap();
+        let ballot = open_ballot(&chain, None, packing).await;
+        let alice = chain.ballot(ballot.address(), Address::repeat_byte(0xa1));
+        let bob = chain.ballot(ballot.address(), Address::repeat_byte(0xb0));
+
+        alice.vote(encrypt(&alice, &[1, 0]).await, Vec::new()).await.unwrap();
+        bob.vote(encrypt(&bob, &[0, 1]).await, Vec::new()).await.unwrap();
+        // Replacing a vote takes the old one back out of the tallies
+        bob.change_vote(encrypt(&bob, &[1, 0]).await, Vec::new()).await.unwrap();
+        assert!(bob.has_voted(bob.sender).await.unwrap());
+
+        assert!(ballot.get_results().await.is_err(), "results before closing");
+        ballot.close().await.unwrap();
+        assert_eq!(ballot.get_results().await.unwrap(), [2, 0]);
+        assert_eq!(ballot.phase().await.unwrap(), Phase::Closed);
+    }
+
+    #[tokio::test]
+    async fn runs_an_election() {
+        election(None).await;
+    }
+
+    #[tokio::test]
+    async fn runs_a_packed_election() {
+        election(Some(Packing { max_voters: 2, max_vote: 1 })).await;
+    }
+
+    #[tokio::test]
+    async fn enforces_phases() {
+        let chain = MockChain::new().unwrap();
+        let ballot = open_ballot(&chain, None, None).await;
+        let voter = chain.ballot(ballot.address(), Address::repeat_byte(0xa1));
+
+        assert!(ballot.add_proposal("c".into(), "late".into()).await.is_err());
+        assert!(voter.add_proposal("c".into(), "not theirs".into()).await.is_err());
+        assert!(voter.change_vote(encrypt(&voter, &[1, 0]).await, Vec::new()).await.is_err());
+        assert!(voter.close().await.is_err(), "only the organiser closes early");
+        ballot.close().await.unwrap();
+        assert!(voter.vote(encrypt(&voter, &[1, 0]).await, Vec::new()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn adds_proposals_all_or_none() {
+        // Room for four proposals in the packed count
+        let packing = Packing { max_voters: 1 << 62, max_vote: 1 };
+        let chain = MockChain::new().unwrap();
+        let deployed = chain.deploy(format!("{ORGANISER:?}"), None, Some(packing)).unwrap();
+        let address = Address::from_str(&deployed.contract_address.unwrap()).unwrap();
+        let ballot = chain.ballot(address, ORGANISER);
+        let proposals =
+            |count: usize| (0..count).map(|i| (i.to_string(), String::new())).collect::<Vec<_>>();
+
+        assert!(ballot.add_proposals(proposals(5)).await.is_err());
+        assert!(ballot.get_proposals().await.unwrap().is_empty(), "no room for the fifth");
+        let voter = chain.ballot(address, Address::repeat_byte(0xa1));
+        assert!(voter.add_proposals(proposals(1)).await.is_err(), "only the organiser adds");
+        ballot.add_proposals(proposals(4)).await.unwrap();
+        assert_eq!(ballot.get_proposals().await.unwrap().len(), 4);
+    }
+
+    #[tokio::test]
+    async fn enforces_the_allowlist() {
+        let (alice, bob) = (Address::repeat_byte(0xa1), Address::repeat_byte(0xb0));
+        let allowlist = Allowlist::new([alice]).unwrap();
+        let chain = MockChain::new().unwrap();
+        let ballot = open_ballot(&chain, Some(allowlist.root_hash()), None).await;
+
+        let proof = allowlist.proof_for(allowlist.root_hash(), alice).unwrap();
+        let alice = chain.ballot(ballot.address(), alice);
+        alice.vote(encrypt(&alice, &[1, 0]).await, proof.clone()).await.unwrap();
+        let bob = chain.ballot(ballot.address(), bob);
+        assert!(bob.vote(encrypt(&bob, &[1, 0]).await, proof).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn accepts_each_signed_vote_once() {
+        let chain = MockChain::new().unwrap();
+        let ballot = open_ballot(&chain, None, None).await;
+        let wallet = LocalWallet::new(&mut ethers::prelude::rand::thread_rng());
+
+        let votes = encrypt(&ballot, &[0, 1]).await;
+        let vote = sign_vote(&wallet, &ballot, votes, Vec::new()).await.unwrap();
+        ballot.vote_by_sig(vote.clone()).await.unwrap();
+        assert!(ballot.has_voted(wallet.address()).await.unwrap());
+        assert!(ballot.vote_by_sig(vote).await.is_err(), "replayed signature");
+    }
+}
@@ -0,0 +1,315 @@
+//! Network profiles.
+//!
+//! A [`NetworkConfig`] describes everything needed to talk to a chain: where its RPC node lives,
+//! its chain id, and a couple of URLs that are only used for messages to the user. The built-in
+//! `local` and `parasol` profiles can be extended (or overridden) with a TOML file of the form
+//!
+//! ```toml
+//! [networks.devnet]
+//! rpc_url = "http://10.0.0.5:8545"
+//! chain_id = 1337
+//! faucet_url = "http://10.0.0.5:8080"
+//! explorer_url = "http://10.0.0.5:4000"
+//! fhe_precompiles = true
+//!
+//! [networks.devnet.transactions]
+//! confirmations = 2
+//! timeout_secs = 600
+//! ```
+//!
+//! where the optional `transactions` table is a [`TxPolicy`]. Any field of the selected profile
+//! can be overridden with the `BALLOT_RPC_URL`, `BALLOT_CHAIN_ID`, `BALLOT_FAUCET_URL`,
+//! `BALLOT_EXPLORER_URL` and `BALLOT_FHE_PRECOMPILES` environment variables, and its
+//! confirmation count and transaction timeout (in seconds) with `BALLOT_CONFIRMATIONS` and
+//! `BALLOT_TX_TIMEOUT`.
+
+use std::{collections::BTreeMap, env, fs, path::Path, str::FromStr, sync::Arc};
+
+use bindings::fhe::{FHE, FHE_DEPLOYED_BYTECODE};
+use ethers::{
+    middleware::SignerMiddleware,
+    providers::{Middleware, RawCall},
+    signers::{coins_bip39::English, LocalWallet, MnemonicBuilder, Signer},
+    types::{spoof, Address},
+    utils::hex,
+};
+use serde::{Deserialize, Serialize};
+use sunscreen_web3::testnet::parasol::PARASOL;
+
+use crate::{middleware, BallotError, BallotMiddleware, ResilientMiddleware, TxPolicy};
+
+/// Connection details for a chain running the ballot contract.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, uniffi::Record)]
+pub struct NetworkConfig {
+    /// Name of the profile, e.g. `parasol`
+    #[serde(default)]
+    pub name: String,
+    /// JSON-RPC endpoint
+    pub rpc_url: String,
+    /// Chain id used when signing transactions
+    pub chain_id: u64,
+    /// Where users can get funds for their wallet, if anywhere
+    #[serde(default)]
+    pub faucet_url: Option<String>,
+    /// Block explorer, if any; the CLI links transactions to `<explorer_url>/tx/<hash>`
+    #[serde(default)]
+    pub explorer_url: Option<String>,
+    /// Whether the node is expected to serve Sunscreen's FHE precompiles. The ballot contract
+    /// cannot be deployed or voted on without them, so deploying and voting fail early with
+    /// [`BallotError::Config`] when this is false, and deploying also probes the node for them.
+    #[serde(default = "default_fhe_precompiles")]
+    pub fhe_precompiles: bool,
+    /// How transactions are sent and waited for
+    #[serde(default)]
+    pub transactions: TxPolicy,
+}
+
+fn default_fhe_precompiles() -> bool {
+    true
+}
+
+/// The `networks` table of a network config file.
+#[derive(Debug, Default, Deserialize)]
+struct NetworkFile {
+    #[serde(default)]
+    networks: BTreeMap<String, NetworkConfig>,
+}
+
+impl NetworkConfig {
+    pub const LOCAL: &'static str = "local";
+    pub const PARASOL: &'static str = "parasol";
+
+    /// Mnemonic Anvil derives its pre-funded dev accounts from.
+    const ANVIL_MNEMONIC: &'static str =
+        "test test test test test test test test test test test junk";
+
+    /// Locally running Sunscreen Anvil fork at http://localhost:8545
+    pub fn local() -> Self {
+        Self {
+            name: Self::LOCAL.to_owned(),
+            rpc_url: "http://localhost:8545".to_owned(),
+            chain_id: 31337,
+            faucet_url: None,
+            explorer_url: None,
+            fhe_precompiles: true,
+            transactions: TxPolicy::default(),
+        }
+    }
+
+    /// Parasol network
+    pub fn parasol() -> Self {
+        Self {
+            name: Self::PARASOL.to_owned(),
+            rpc_url: PARASOL.rpc_url.to_owned(),
+            chain_id: PARASOL.chain_id,
+            faucet_url: Some(PARASOL.faucet_url.to_owned()),
+            explorer_url: None,
+            fhe_precompiles: true,
+            transactions: TxPolicy::default(),
+        }
+    }
+
+    /// Look up a network by name.
+    ///
+    /// Profiles in `file` take precedence over the built-in ones, and environment variables take
+    /// precedence over both.
+    pub fn resolve(name: &str, file: Option<&Path>) -> Result<Self, BallotError> {
+        let mut networks = match file {
+            Some(path) => {
+                let contents = fs::read_to_string(path).map_err(|e| {
+                    BallotError::config(format!("could not read {}: {e}", path.display()))
+                })?;
+                Self::parse_file(&contents)?
+            }
+            None => BTreeMap::new(),
+        };
+
+        let config = match networks.remove(name) {
+            Some(config) => config,
+            None => match name {
+                Self::LOCAL => Self::local(),
+                Self::PARASOL => Self::parasol(),
+                _ => return Err(BallotError::config(format!("unknown network {name:?}"))),
+            },
+        };
+
+        config.with_env_overrides()
+    }
+
+    /// Parse the `networks` table of a TOML config file.
+    fn parse_file(contents: &str) -> Result<BTreeMap<String, Self>, BallotError> {
+        let file: NetworkFile = toml::from_str(contents).map_err(BallotError::config)?;
+        Ok(file
+            .networks
+            .into_iter()
+            .map(|(name, mut config)| {
+                config.name = name.clone();
+                (name, config)
+            })
+            .collect())
+    }
+
+    /// Apply any `BALLOT_*` environment variable overrides.
+    pub fn with_env_overrides(mut self) -> Result<Self, BallotError> {
+        if let Some(rpc_url) = env_var("BALLOT_RPC_URL") {
+            self.rpc_url = rpc_url;
+        }
+        if let Some(chain_id) = env_var("BALLOT_CHAIN_ID") {
+            self.chain_id = parse_env("BALLOT_CHAIN_ID", &chain_id)?;
+        }
+        if let Some(faucet_url) = env_var("BALLOT_FAUCET_URL") {
+            self.faucet_url = Some(faucet_url);
+        }
+        if let Some(explorer_url) = env_var("BALLOT_EXPLORER_URL") {
+            self.explorer_url = Some(explorer_url);
+        }
+        if let Some(fhe_precompiles) = env_var("BALLOT_FHE_PRECOMPILES") {
+            self.fhe_precompiles = parse_env("BALLOT_FHE_PRECOMPILES", &fhe_precompiles)?;
+        }
+        if let Some(confirmations) = env_var("BALLOT_CONFIRMATIONS") {
+            self.transactions.confirmations = parse_env("BALLOT_CONFIRMATIONS", &confirmations)?;
+        }
+        if let Some(timeout) = env_var("BALLOT_TX_TIMEOUT") {
+            self.transactions.timeout_secs = parse_env("BALLOT_TX_TIMEOUT", &timeout)?;
+        }
+        Ok(self)
+    }
+
+    /// Fail unless the network is expected to serve the FHE precompiles, without which ballots
+    /// can't be deployed or voted on.
+    pub fn ensure_fhe_precompiles(&self) -> Result<(), BallotError> {
+        if self.fhe_precompiles {
+            Ok(())
+        } else {
+            Err(BallotError::config(format!(
+                "network {:?} has no FHE precompiles, so it can't run ballots",
+                self.name
+            )))
+        }
+    }
+
+    /// Like [`NetworkConfig::ensure_fhe_precompiles`], and also check that the node really serves
+    /// them, by reading the network key through an `FHE` contract overlaid on a throwaway address.
+    pub async fn probe_fhe_precompiles<M: Middleware + 'static>(
+        &self,
+        client: Arc<M>,
+    ) -> Result<(), BallotError> {
+        self.ensure_fhe_precompiles()?;
+        let address = Address::repeat_byte(0xfe);
+        let state = spoof::code(address, FHE_DEPLOYED_BYTECODE.clone());
+        FHE::new(address, client).network_public_key().call_raw().state(&state).await.map_err(
+            |e| {
+                BallotError::config(format!(
+                    "network {:?} doesn't serve the FHE precompiles: {e}",
+                    self.name
+                ))
+            },
+        )?;
+        Ok(())
+    }
+
+    /// Where the block explorer shows the transaction `tx_hash`, if the network has one.
+    pub fn explorer_tx_url(&self, tx_hash: &str) -> Option<String> {
+        let explorer_url = self.explorer_url.as_deref()?;
+        Some(format!("{}/tx/{tx_hash}", explorer_url.trim_end_matches('/')))
+    }
+
+    /// Build a signing client for `wallet` on this network, which sends transactions as the
+    /// network's [`TxPolicy`] says.
+    pub fn client(&self, wallet: LocalWallet) -> Result<Arc<BallotMiddleware>, BallotError> {
+        let provider = middleware::provider(&self.rpc_url, &self.transactions)?;
+        let signer = SignerMiddleware::new(provider, wallet.with_chain_id(self.chain_id));
+        Ok(Arc::new(ResilientMiddleware::new(signer, self.transactions.clone())))
+    }
+}
+
+fn env_var(key: &str) -> Option<String> {
+    env::var(key).ok().filter(|value| !value.is_empty())
+}
+
+fn parse_env<T: FromStr>(key: &str, value: &str) -> Result<T, BallotError>
+where
+    T::Err: std::fmt::Display,
+{
+    value.parse().map_err(|e| BallotError::config(format!("invalid {key} {value:?}: {e}")))
+}
+
+/// Look up a built-in network profile (`local` or `parasol`), applying environment overrides.
+#[uniffi::export]
+pub fn network_config(name: String) -> Result<NetworkConfig, BallotError> {
+    NetworkConfig::resolve(&name, None)
+}
+
+/// Look up a network profile from the contents of a TOML config file, falling back to the
+/// built-in profiles.
+#[uniffi::export]
+pub fn network_config_from_toml(
+    contents: String,
+    name: String,
+) -> Result<NetworkConfig, BallotError> {
+    let mut networks = NetworkConfig::parse_file(&contents)?;
+    match networks.remove(&name) {
+        Some(config) => config.with_env_overrides(),
+        None => NetworkConfig::resolve(&name, None),
+    }
+}
+
+/// The built-in `local` profile, optionally pointed at an Anvil node other than
+/// http://localhost:8545 (e.g. the development machine's LAN address when running on a device).
+#[uniffi::export]
+pub fn local_network_config(rpc_url: Option<String>) -> Result<NetworkConfig, BallotError> {
+    let mut config = NetworkConfig::local().with_env_overrides()?;
+    if let Some(rpc_url) = rpc_url {
+        config.rpc_url = rpc_url;
+    }
+    Ok(config)
+}
+
+/// Secret key of one of Anvil's pre-funded dev accounts, for use as a wallet key against the
+/// `local` network.
+///
+/// These keys are public knowledge; never use them on a real network.
+#[uniffi::export]
+pub fn anvil_wallet_key(index: u32) -> Result<String, BallotError> {
+    let wallet = MnemonicBuilder::<English>::default()
+        .phrase(NetworkConfig::ANVIL_MNEMONIC)
+        .index(index)
+        .and_then(|builder| builder.build())
+        .map_err(BallotError::key)?;
+    Ok(hex::encode(wallet.signer().to_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_profiles() {
+        let networks = NetworkConfig::parse_file(
+            r#"
+            [networks.devnet]
+            rpc_url = "http://10.0.0.5:8545"
+            chain_id = 1337
+            explorer_url = "http://10.0.0.5:4000/"
+            fhe_precompiles = false
+            "#,
+        )
+        .unwrap();
+        let devnet = &networks["devnet"];
+        assert_eq!(devnet.name, "devnet");
+        assert_eq!(devnet.chain_id, 1337);
+        assert_eq!(
+            devnet.explorer_tx_url("0xabc").as_deref(),
+            Some("http://10.0.0.5:4000/tx/0xabc")
+        );
+        assert!(matches!(devnet.ensure_fhe_precompiles(), Err(BallotError::Config { .. })));
+    }
+
+    #[test]
+    fn built_in_profiles_expect_fhe_precompiles() {
+        for network in [NetworkConfig::local(), NetworkConfig::parasol()] {
+            network.ensure_fhe_precompiles().unwrap();
+            assert_eq!(network.explorer_tx_url("0xabc"), None);
+        }
+    }
+}
@@ -0,0 +1,161 @@
+//! Packed ballots.
+//!
+//! On an ordinary ballot a vote is one `Unsigned64` ciphertext per proposal, and the contract
+//! adds each one to its proposal's count, so both the calldata and the FHE work of a vote grow
+//! with the number of proposals. A packed ballot keeps every count in a single `Unsigned256`
+//! instead, proposal `i` in the `slot_bits` wide slot starting at bit `i * slot_bits`. A vote is
+//! packed the same way into one ciphertext, and one homomorphic addition updates every count.
+//!
+//! Slots are sized when the ballot is deployed, from a [`Packing`] that bounds what a proposal
+//! can get, and the ballot then holds at most `256 / slot_bits` proposals.
+//!
+//! # Limitations
+//!
+//! The contract can't see inside a ciphertext, so it accepts any packed vote, and nothing on
+//! chain stops a count overflowing its slot into the next proposal's. [`pack`] rejects a vote
+//! that doesn't fit its own slot, but that is all a client can check: the ballot only records
+//! `slot_bits`, not the [`Packing`] it was sized from, so a client can't tell a vote above
+//! `max_vote` from a legitimate one. The counts are only as sound as the voters' clients, and
+//! voters should be held to a [`VotingRule`](crate::VotingRule) that gives no proposal more than
+//! `max_vote`. A voter who bypasses their client can corrupt every count on a packed ballot.
+
+use ethers::types::U256;
+use sunscreen::types::bfv::Unsigned256;
+
+use crate::BallotError;
+
+/// The most a packed ballot has to count, which sizes its slots.
+///
+/// Neither bound is enforced: not by the contract, and not by other voters' clients, which only
+/// see the resulting slot width. See the [module docs](self) for what that means for the counts.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, uniffi::Record)]
+pub struct Packing {
+    /// Most voters the ballot will have, e.g. the size of its allowlist
+    pub max_voters: u64,
+    /// Most votes one voter can give one proposal: 1 for single choice and approval ballots,
+    /// the budget for budgeted ones
+    pub max_vote: u64,
+}
+
+impl Packing {
+    /// Bits per slot: enough for every voter to give the same proposal their largest vote.
+    pub fn slot_bits(&self) -> Result<u8, BallotError> {
+        let most = self
+            .max_voters
+            .checked_mul(self.max_vote)
+            .ok_or_else(|| BallotError::config("packed counts would not fit in 64 bits"))?;
+        Ok((u64::BITS - most.leading_zeros()).max(1) as u8)
+    }
+
+    /// Most proposals a ballot packed like this can have.
+    pub fn max_proposals(&self) -> Result<usize, BallotError> {
+        Ok(256 / usize::from(self.slot_bits()?))
+    }
+}
+
+/// Pack one vote per proposal into `slot_bits` wide slots.
+///
+/// Fails if a vote is `2^slot_bits` or more, since it would spill into the next proposal's slot.
+/// A vote that fits can still overflow its slot once added to the other voters'; this only stops
+/// a single vote doing so on its own.
+pub fn pack(votes: &[u64], slot_bits: u8) -> Result<U256, BallotError> {
+    let slot_bits = usize::from(slot_bits);
+    if votes.len() * slot_bits > 256 {
+        return Err(BallotError::invalid_vote(format!(
+            "{} proposals don't fit in {slot_bits} bit slots",
+            votes.len()
+        )));
+    }
+    votes.iter().enumerate().try_fold(U256::zero(), |packed, (i, &vote)| {
+        if slot_bits < 64 && vote >> slot_bits != 0 {
+            return Err(BallotError::invalid_vote(format!(
+                "vote {vote} for proposal {i} doesn't fit in a {slot_bits} bit slot"
+            )));
+        }
+        Ok(packed | U256::from(vote) << (i * slot_bits))
+    })
+}
+
+/// Split a packed count back into `proposal_count` counts of `slot_bits` bits each.
+pub fn unpack(packed: U256, slot_bits: u8, proposal_count: usize) -> Vec<u64> {
+    let slot_bits = usize::from(slot_bits);
+    let mask = (U256::one() << slot_bits) - 1;
+    (0..proposal_count).map(|i| ((packed >> (i * slot_bits)) & mask).low_u64()).collect()
+}
+
+/// One tally per proposal, from what `getProposalTallys` decrypted to: the tallies themselves, or
+/// on a packed ballot the packed count.
+pub fn unpack_tallies(
+    tallies: Vec<Unsigned256>,
+    slot_bits: u8,
+    proposal_count: usize,
+) -> Result<Vec<U256>, BallotError> {
+    if slot_bits == 0 {
+        return Ok(tallies.into_iter().map(from_unsigned).collect());
+    }
+    let count = tallies.len();
+    let [packed] = <[Unsigned256; 1]>::try_from(tallies).map_err(|_| {
+        BallotError::decrypt(format!("a packed ballot has one tally, but {count} came back"))
+    })?;
+    let counts = unpack(from_unsigned(packed), slot_bits, proposal_count);
+    Ok(counts.into_iter().map(U256::from).collect())
+}
+
+pub(crate) fn to_unsigned(value: U256) -> Unsigned256 {
+    crypto_bigint::Uint::<4>::from_words(value.0).into()
+}
+
+pub(crate) fn from_unsigned(value: Unsigned256) -> U256 {
+    U256(crypto_bigint::Uint::<4>::from(value).to_words())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slot_bits_fit_the_largest_count() {
+        assert_eq!(Packing { max_voters: 100, max_vote: 1 }.slot_bits().unwrap(), 7);
+        assert_eq!(Packing { max_voters: 128, max_vote: 1 }.slot_bits().unwrap(), 8);
+        assert_eq!(Packing { max_voters: 0, max_vote: 1 }.slot_bits().unwrap(), 1);
+        assert_eq!(Packing { max_voters: 128, max_vote: 1 }.max_proposals().unwrap(), 32);
+        assert!(Packing { max_voters: u64::MAX, max_vote: 2 }.slot_bits().is_err());
+    }
+
+    #[test]
+    fn round_trip() {
+        let votes = [3, 0, 255, 17, 1];
+        assert_eq!(unpack(pack(&votes, 8).unwrap(), 8, votes.len()), votes);
+
+        let votes = [u64::MAX, 0, 1, u64::MAX];
+        assert_eq!(unpack(pack(&votes, 64).unwrap(), 64, votes.len()), votes);
+    }
+
+    #[test]
+    fn slot_boundary() {
+        assert!(pack(&[0, 15], 4).is_ok());
+        assert!(matches!(pack(&[0, 16], 4), Err(BallotError::InvalidVote { .. })));
+        assert!(pack(&[0; 32], 8).is_ok());
+        assert!(matches!(pack(&[0; 33], 8), Err(BallotError::InvalidVote { .. })));
+    }
+
+    #[test]
+    fn sums_stay_in_their_slots() {
+        let a = pack(&[1, 2, 3], 8).unwrap();
+        let b = pack(&[4, 5, 6], 8).unwrap();
+        assert_eq!(unpack(a + b, 8, 3), [5, 7, 9]);
+    }
+
+    #[test]
+    fn unpacks_tallies() {
+        let packed = to_unsigned(pack(&[2, 9], 8).unwrap());
+        let tallies = unpack_tallies(vec![packed], 8, 2).unwrap();
+        assert_eq!(tallies, [U256::from(2), U256::from(9)]);
+
+        let tallies = vec![to_unsigned(U256::from(4)), to_unsigned(U256::from(6))];
+        assert_eq!(unpack_tallies(tallies, 0, 2).unwrap(), [U256::from(4), U256::from(6)]);
+
+        let tallies = vec![to_unsigned(U256::one()), to_unsigned(U256::one())];
+        assert!(matches!(unpack_tallies(tallies, 8, 2), Err(BallotError::Decrypt { .. })));
+    }
+}
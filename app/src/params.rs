@@ -0,0 +1,98 @@
+//! FHE parameter compatibility checks.
+//!
+//! Votes are encrypted under the contract's network key, and tallies are re-encrypted under the
+//! voter's local key, but both go through the same client side [`RUNTIME`]. If any of the three
+//! disagree on parameters, encryption silently produces ciphertexts the contract can't use, and
+//! decryption fails with an error that says nothing about why. Checking up front turns both into
+//! a readable diff.
+
+use ethers::utils::{hex, keccak256};
+use sunscreen::{Params, PublicKey};
+use sunscreen_web3::testnet::parasol::RUNTIME;
+
+use crate::BallotError;
+
+/// Short, stable identifier of a set of FHE parameters.
+pub fn fingerprint(params: &Params) -> String {
+    hex::encode(&keccak256(params.to_bytes())[..8])
+}
+
+/// Field by field differences between two sets of parameters, one line per differing field.
+pub(crate) fn diff(left: (&str, &Params), right: (&str, &Params)) -> Vec<String> {
+    let ((left_name, left), (right_name, right)) = (left, right);
+    let fields = [
+        (
+            "lattice_dimension",
+            left.lattice_dimension.to_string(),
+            right.lattice_dimension.to_string(),
+        ),
+        (
+            "coeff_modulus",
+            format!("{:?}", left.coeff_modulus),
+            format!("{:?}", right.coeff_modulus),
+        ),
+        ("plain_modulus", left.plain_modulus.to_string(), right.plain_modulus.to_string()),
+        ("scheme_type", format!("{:?}", left.scheme_type), format!("{:?}", right.scheme_type)),
+        (
+            "security_level",
+            format!("{:?}", left.security_level),
+            format!("{:?}", right.security_level),
+        ),
+    ];
+
+    fields
+        .into_iter()
+        .filter(|(_, left, right)| left != right)
+        .map(|(field, left, right)| {
+            format!("{field}: {left_name} has {left}, {right_name} has {right}")
+        })
+        .collect()
+}
+
+/// The parameters of the local keys, the contract's network key and the client runtime.
+#[derive(Clone, Debug, uniffi::Record)]
+pub struct ParamsReport {
+    /// Fingerprint of the runtime parameters used to encrypt votes and decrypt tallies
+    pub runtime: String,
+    /// Fingerprint of the local public key's parameters
+    pub local_key: String,
+    /// Fingerprint of the contract's network public key's parameters
+    pub network_key: String,
+    /// One line per mismatched field; empty if everything is compatible
+    pub differences: Vec<String>,
+}
+
+impl ParamsReport {
+    /// Compare `local_key` and `network_key` against the runtime.
+    ///
+    /// The runtime is the reference: it does all the encrypting and decrypting, so both keys
+    /// have to match it (and therefore each other).
+    pub fn new(local_key: &PublicKey, network_key: &PublicKey) -> Self {
+        let runtime = RUNTIME.params();
+        let local = &local_key.public_key.params;
+        let network = &network_key.public_key.params;
+
+        let mut differences = diff(("runtime", runtime), ("local key", local));
+        differences.extend(diff(("runtime", runtime), ("network key", network)));
+
+        Self {
+            runtime: fingerprint(runtime),
+            local_key: fingerprint(local),
+            network_key: fingerprint(network),
+            differences,
+        }
+    }
+
+    pub fn is_compatible(&self) -> bool {
+        self.differences.is_empty()
+    }
+
+    /// Turn any differences into a [`BallotError::Params`].
+    pub fn ensure_compatible(self) -> Result<Self, BallotError> {
+        if self.is_compatible() {
+            Ok(self)
+        } else {
+            Err(BallotError::params(self.differences.join("; ")))
+        }
+    }
+}
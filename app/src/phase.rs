@@ -0,0 +1,61 @@
+//! The ballot lifecycle.
+//!
+//! A ballot starts in [`Phase::Setup`], where proposals are added. The organiser opens voting
+//! with a deadline, and closing it, early by the organiser or by anyone once the deadline has
+//! passed, decrypts the tallies and publishes them as plaintext results.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::BallotError;
+
+/// Where a ballot is in its lifecycle, as stored by the contract.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, uniffi::Enum)]
+pub enum Phase {
+    /// Proposals can be added; nobody can vote yet.
+    #[default]
+    Setup,
+    /// Votes can be cast until the deadline; the proposals are fixed.
+    Voting,
+    /// Voting is over and the results are public.
+    Closed,
+}
+
+impl Phase {
+    /// Fail with [`BallotError::WrongPhase`] unless `actual` is this phase. `action` names what
+    /// was attempted, for the message.
+    pub fn ensure(self, actual: Phase, action: &str) -> Result<(), BallotError> {
+        if actual == self {
+            Ok(())
+        } else {
+            Err(BallotError::wrong_phase(format!(
+                "can't {action} in the {actual} phase, only in {self}"
+            )))
+        }
+    }
+}
+
+impl TryFrom<u8> for Phase {
+    type Error = BallotError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::Setup),
+            1 => Ok(Self::Voting),
+            2 => Ok(Self::Closed),
+            _ => Err(BallotError::serialization(format!("unknown ballot phase {value}"))),
+        }
+    }
+}
+
+impl fmt::Display for Phase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Setup => "setup",
+            Self::Voting => "voting",
+            Self::Closed => "closed",
+        };
+        f.write_str(name)
+    }
+}
@@ -0,0 +1,216 @@
+//! Gasless voting.
+//!
+//! Sending `Ballot.vote` costs gas, so a voter needs funds before they can vote. Instead, a voter
+//! can sign their encrypted votes as EIP-712 typed data and hand them to a relayer, such as
+//! `ballot-relayer`, which submits them through `Ballot.voteBySig` and pays for the transaction.
+//! The signed message is
+//!
+//! ```text
+//! Vote(address voter,bytes[] votes,uint256 nonce,uint256 deadline)
+//! ```
+//!
+//! in the domain named `Ballot`, version `1`, of the ballot's chain and address. `nonce` counts
+//! the voter's signed votes on the ballot so far, so each signature is good for one vote, and
+//! `deadline` is the Unix timestamp after which it's no good at all. The allowlist proof isn't
+//! signed; whoever submits the vote can only ever use it to show the voter's own eligibility.
+//!
+//! Relayers take a [`SignedVote`] as JSON in a `POST` to `/votes`, and answer with the
+//! [`TxOutcome`] of the transaction, or with a [`RelayError`] if they refuse the vote.
+
+use bindings::ballot::Ballot;
+use ethers::{
+    abi::{self, Token},
+    contract::builders::ContractCall,
+    providers::Middleware,
+    signers::{LocalWallet, Signer},
+    types::{Address, Bytes, Signature, H256},
+    utils::keccak256,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{BallotBackend, BallotError, EncVotes, TxOutcome};
+
+const DOMAIN_TYPE: &str =
+    "EIP712Domain(string name,string version,uint256 chainId,address verifyingContract)";
+const VOTE_TYPE: &str = "Vote(address voter,bytes[] votes,uint256 nonce,uint256 deadline)";
+
+/// The EIP-712 domain separator of the contract at `verifying_contract` on chain `chain_id`.
+fn domain_separator(
+    name: &str,
+    version: &str,
+    chain_id: u64,
+    verifying_contract: Address,
+) -> [u8; 32] {
+    keccak256(abi::encode(&[
+        Token::FixedBytes(keccak256(DOMAIN_TYPE).to_vec()),
+        Token::FixedBytes(keccak256(name).to_vec()),
+        Token::FixedBytes(keccak256(version).to_vec()),
+        Token::Uint(chain_id.into()),
+        Token::Address(verifying_contract),
+    ]))
+}
+
+/// Votes signed by their voter, for anyone to submit on the voter's behalf.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SignedVote {
+    /// Address of the ballot the votes are for
+    pub ballot: Address,
+    pub voter: Address,
+    /// One encrypted vote per proposal, or the packed vote on a packed ballot
+    pub votes: Vec<Bytes>,
+    /// Allowlist proof for the voter; empty if the ballot has no allowlist
+    pub proof: Vec<H256>,
+    /// How many signed votes the voter had cast on the ballot when signing
+    pub nonce: u64,
+    /// Unix timestamp after which the ballot no longer accepts the signature
+    pub deadline: u64,
+    pub signature: Bytes,
+}
+
+impl SignedVote {
+    /// Sign `votes` on `ballot`, on chain `chain_id`, as `wallet`.
+    pub fn sign(
+        wallet: &LocalWallet,
+        chain_id: u64,
+        ballot: Address,
+        votes: EncVotes,
+        proof: Vec<H256>,
+        nonce: u64,
+        deadline: u64,
+    ) -> Result<Self, BallotError> {
+        let mut vote = Self {
+            ballot,
+            voter: wallet.address(),
+            votes: votes.into_bytes(),
+            proof,
+            nonce,
+            deadline,
+            signature: Bytes::new(),
+        };
+        let signature = wallet.sign_hash(vote.digest(chain_id)).map_err(BallotError::key)?;
+        vote.signature = signature.to_vec().into();
+        Ok(vote)
+    }
+
+    /// The EIP-712 digest the voter signs, as `voteBySig` computes it on chain `chain_id`.
+    pub fn digest(&self, chain_id: u64) -> H256 {
+        let domain = domain_separator("Ballot", "1", chain_id, self.ballot);
+        // An array of `bytes` hashes each element, then the concatenated hashes
+        let votes: Vec<u8> = self.votes.iter().flat_map(keccak256).collect();
+        let vote = keccak256(abi::encode(&[
+            Token::FixedBytes(keccak256(VOTE_TYPE).to_vec()),
+            Token::Address(self.voter),
+            Token::FixedBytes(keccak256(votes).to_vec()),
+            Token::Uint(self.nonce.into()),
+            Token::Uint(self.deadline.into()),
+        ]));
+        keccak256([&[0x19, 0x01][..], &domain, &vote].concat()).into()
+    }
+
+    /// Check that the voter signed the votes for chain `chain_id`, failing like `voteBySig`
+    /// does if not.
+    pub fn verify(&self, chain_id: u64) -> Result<(), BallotError> {
+        let signer = Signature::try_from(self.signature.as_ref())
+            .and_then(|signature| signature.recover(self.digest(chain_id)));
+        match signer {
+            Ok(signer) if signer == self.voter => Ok(()),
+            _ => Err(BallotError::revert("Invalid signature")),
+        }
+    }
+
+    /// The `voteBySig` call that submits the votes to `ballot`.
+    pub fn into_call<M: Middleware>(self, ballot: &Ballot<M>) -> ContractCall<M, ()> {
+        let proof = self.proof.into_iter().map(H256::to_fixed_bytes).collect();
+        ballot.vote_by_sig(self.voter, self.votes, proof, self.deadline.into(), self.signature)
+    }
+}
+
+/// Why a relayer refused a vote.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RelayError {
+    pub error: String,
+}
+
+/// Sign encrypted `votes` on `ballot` as `wallet`, for a relayer to submit. The signature is good
+/// until voting ends.
+pub async fn sign_vote(
+    wallet: &LocalWallet,
+    ballot: &dyn BallotBackend,
+    votes: EncVotes,
+    proof: Vec<H256>,
+) -> Result<SignedVote, BallotError> {
+    let chain_id = ballot.chain_id().await?;
+    let nonce = ballot.nonce(wallet.address()).await?;
+    let deadline = ballot.voting_ends().await?;
+    SignedVote::sign(wallet, chain_id, ballot.address(), votes, proof, nonce, deadline)
+}
+
+/// Hand `vote` to the relayer at `relayer_url`, and wait for it to be mined.
+pub async fn relay(relayer_url: &str, vote: &SignedVote) -> Result<TxOutcome, BallotError> {
+    let url = format!("{}/votes", relayer_url.trim_end_matches('/'));
+    let response = reqwest::Client::new()
+        .post(&url)
+        .json(vote)
+        .send()
+        .await
+        .map_err(|e| BallotError::relay(format!("could not reach {url}: {e}")))?;
+
+    let status = response.status();
+    if status.is_success() {
+        return response.json().await.map_err(BallotError::relay);
+    }
+    match response.json::<RelayError>().await {
+        Ok(rejection) => Err(BallotError::relay(format!("{status}: {}", rejection.error))),
+        Err(_) => Err(BallotError::relay(status)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vote() -> SignedVote {
+        SignedVote {
+            ballot: Address::repeat_byte(0xab),
+            voter: Address::repeat_byte(0xcd),
+            votes: vec![vec![1, 2, 3].into(), (0..40).collect::<Vec<u8>>().into()],
+            proof: Vec::new(),
+            nonce: 3,
+            deadline: 1_700_000_000,
+            signature: Bytes::new(),
+        }
+    }
+
+    /// The `Mail` example domain from EIP-712 itself.
+    #[test]
+    fn domain_separator_matches_eip712_example() {
+        let contract = Address::repeat_byte(0xcc);
+        assert_eq!(
+            H256(domain_separator("Ether Mail", "1", 1, contract)),
+            "0xf2cee375fa42b42143804025fc449deafd50cc031ca257e0b194a650a912090f".parse().unwrap()
+        );
+    }
+
+    #[test]
+    fn digest_matches_fixed_vector() {
+        assert_eq!(
+            vote().digest(574),
+            "0x15c225e518c9a40eba88aa7c63275f66a099dcc3982188fc1934abe578087b61".parse().unwrap()
+        );
+    }
+
+    #[test]
+    fn signature_round_trip() {
+        let wallet = LocalWallet::new(&mut ethers::prelude::rand::thread_rng());
+        let mut vote = SignedVote { voter: wallet.address(), ..vote() };
+        let signature = wallet.sign_hash(vote.digest(574)).unwrap();
+        vote.signature = signature.to_vec().into();
+        vote.verify(574).unwrap();
+
+        assert!(vote.verify(1).is_err(), "signed for another chain");
+        let other_ballot = SignedVote { ballot: Address::repeat_byte(0x01), ..vote.clone() };
+        assert!(other_ballot.verify(574).is_err(), "signed for another ballot");
+        let replayed = SignedVote { nonce: vote.nonce + 1, ..vote.clone() };
+        assert!(replayed.verify(574).is_err(), "signed for another nonce");
+    }
+}
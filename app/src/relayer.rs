@@ -0,0 +1,246 @@
+//! Submits signed votes on voters' behalf, paying the gas from its own wallet.
+//!
+//! Voters `POST` a [`SignedVote`] as JSON to `/votes`. The relayer only pays for votes on the
+//! ballots it was started with, checks the signature and the voter's nonce, and simulates the
+//! transaction before sending it, so a vote the ballot would reject costs nothing. Requests are
+//! rate limited per client IP address, and signed votes per voter.
+
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    env,
+    hash::Hash,
+    net::{IpAddr, SocketAddr},
+    path::PathBuf,
+    str::FromStr,
+    sync::{Arc, Mutex, PoisonError},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use axum::{
+    extract::{ConnectInfo, DefaultBodyLimit, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::post,
+    Json, Router,
+};
+use bindings::ballot::Ballot;
+use clap::Parser;
+use ethers::{
+    signers::{LocalWallet, Signer},
+    types::Address,
+};
+use eyre::{bail, Result};
+use sunscreen_ballot::{
+    BallotError, BallotMiddleware, NetworkConfig, RelayError, SignedVote, TxOutcome,
+};
+use tokio::net::TcpListener;
+
+/// Largest request accepted. Every vote is a full ciphertext, hex encoded, so a ballot with many
+/// proposals makes for a big request.
+const MAX_REQUEST_BYTES: usize = 32 * 1024 * 1024;
+
+#[derive(Parser, Debug)]
+struct Args {
+    /// Network to submit votes on: `parasol`, `local` (an Anvil node at http://localhost:8545),
+    /// or any profile defined in --network-config
+    #[arg(short, long, default_value = NetworkConfig::PARASOL)]
+    network: String,
+
+    /// TOML file with additional network profiles
+    #[arg(long)]
+    network_config: Option<PathBuf>,
+
+    /// RPC endpoint (override whatever the network profile says)
+    #[arg(long)]
+    rpc_url: Option<String>,
+
+    /// Key of the wallet that pays for relayed votes
+    ///
+    /// Read from `BALLOT_RELAYER_KEY` if not given, which keeps it out of the process list.
+    #[arg(short, long)]
+    wallet_key: Option<String>,
+
+    /// Address to listen on
+    #[arg(short, long, default_value = "127.0.0.1:8080")]
+    listen: SocketAddr,
+
+    /// Ballot to relay votes for; votes on any other contract are refused. Repeat for each ballot
+    #[arg(long = "ballot", required = true)]
+    ballots: Vec<Address>,
+
+    /// Most votes relayed per voter in each --window
+    #[arg(long, default_value_t = 5)]
+    voter_limit: usize,
+
+    /// Most requests accepted per client IP address in each --window
+    #[arg(long, default_value_t = 50)]
+    ip_limit: usize,
+
+    /// Length of the rate limiting window, in seconds
+    #[arg(long, default_value_t = 3600)]
+    window: u64,
+}
+
+/// How many requests each key has made within a sliding window.
+struct RateLimiter<K> {
+    limit: usize,
+    window: Duration,
+    requests: Mutex<HashMap<K, VecDeque<Instant>>>,
+}
+
+impl<K: Hash + Eq> RateLimiter<K> {
+    fn new(limit: usize, window: Duration) -> Self {
+        Self { limit, window, requests: Mutex::default() }
+    }
+
+    /// Count a request from `key`, unless it has already made its limit within the window.
+    fn check(&self, key: K) -> bool {
+        let now = Instant::now();
+        let mut requests = self.requests.lock().unwrap_or_else(PoisonError::into_inner);
+        for times in requests.values_mut() {
+            while times.front().is_some_and(|&time| now.duration_since(time) >= self.window) {
+                times.pop_front();
+            }
+        }
+        requests.retain(|_, times| !times.is_empty());
+
+        let times = requests.entry(key).or_default();
+        if times.len() >= self.limit {
+            return false;
+        }
+        times.push_back(now);
+        true
+    }
+}
+
+/// A voter with a vote being submitted, until dropped.
+struct InFlight<'a> {
+    voters: &'a Mutex<HashSet<Address>>,
+    voter: Address,
+}
+
+impl<'a> InFlight<'a> {
+    /// Mark `voter` as in flight, unless they already are.
+    fn start(voters: &'a Mutex<HashSet<Address>>, voter: Address) -> Option<Self> {
+        let started = voters.lock().unwrap_or_else(PoisonError::into_inner).insert(voter);
+        started.then_some(Self { voters, voter })
+    }
+}
+
+impl Drop for InFlight<'_> {
+    fn drop(&mut self) {
+        self.voters.lock().unwrap_or_else(PoisonError::into_inner).remove(&self.voter);
+    }
+}
+
+struct Relayer {
+    client: Arc<BallotMiddleware>,
+    chain_id: u64,
+    ballots: HashSet<Address>,
+    voters: RateLimiter<Address>,
+    ips: RateLimiter<IpAddr>,
+    /// Voters with a vote being submitted; a second one would only fail on its nonce
+    in_flight: Mutex<HashSet<Address>>,
+}
+
+/// A refused vote: the status to answer with, and why.
+struct Rejection(StatusCode, String);
+
+impl From<BallotError> for Rejection {
+    fn from(e: BallotError) -> Self {
+        match e {
+            BallotError::Revert { reason } => Self(StatusCode::UNPROCESSABLE_ENTITY, reason),
+            e => Self(StatusCode::BAD_GATEWAY, e.to_string()),
+        }
+    }
+}
+
+impl IntoResponse for Rejection {
+    fn into_response(self) -> Response {
+        (self.0, Json(RelayError { error: self.1 })).into_response()
+    }
+}
+
+async fn relay_vote(
+    State(relayer): State<Arc<Relayer>>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    Json(vote): Json<SignedVote>,
+) -> Result<Json<TxOutcome>, Rejection> {
+    let voter = vote.voter;
+    if !relayer.ips.check(peer.ip()) {
+        let reason = "too many requests from this address; try again later";
+        return Err(Rejection(StatusCode::TOO_MANY_REQUESTS, reason.into()));
+    }
+    if !relayer.ballots.contains(&vote.ballot) {
+        let reason = format!("votes on {:?} are not relayed here", vote.ballot);
+        return Err(Rejection(StatusCode::FORBIDDEN, reason));
+    }
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |since| since.as_secs());
+    if vote.deadline < now {
+        return Err(Rejection(StatusCode::BAD_REQUEST, "the signature has expired".into()));
+    }
+    if vote.verify(relayer.chain_id).is_err() {
+        let reason = format!("the votes are not signed by {voter:?}");
+        return Err(Rejection(StatusCode::UNAUTHORIZED, reason));
+    }
+
+    // Only signed votes count against a voter, so nobody else can use up their limit
+    if !relayer.voters.check(voter) {
+        let reason = format!("too many votes from {voter:?}; try again later");
+        return Err(Rejection(StatusCode::TOO_MANY_REQUESTS, reason));
+    }
+    let Some(_in_flight) = InFlight::start(&relayer.in_flight, voter) else {
+        let reason = format!("a vote from {voter:?} is already being submitted");
+        return Err(Rejection(StatusCode::CONFLICT, reason));
+    };
+
+    let ballot = Ballot::new(vote.ballot, relayer.client.clone());
+    let nonce = ballot.nonces(voter).call().await.map_err(BallotError::from)?;
+    if nonce != vote.nonce.into() {
+        let reason =
+            format!("the votes are signed with nonce {}, but the next is {nonce}", vote.nonce);
+        return Err(Rejection(StatusCode::CONFLICT, reason));
+    }
+
+    // Simulate first, so votes the ballot would reject aren't paid for
+    let call = vote.into_call(&ballot);
+    call.call().await.map_err(BallotError::from)?;
+    let pending = call.send().await.map_err(BallotError::from)?;
+    let outcome = TxOutcome::confirm(&*relayer.client, pending).await?;
+    eprintln!("Relayed a vote from {voter:?} in {}: {}", outcome.tx_hash, outcome.status);
+    Ok(Json(outcome))
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let args = Args::parse();
+    let mut network = NetworkConfig::resolve(&args.network, args.network_config.as_deref())?;
+    if let Some(rpc_url) = &args.rpc_url {
+        network.rpc_url = rpc_url.clone();
+    }
+    let Some(wallet_key) = args.wallet_key.or_else(|| env::var("BALLOT_RELAYER_KEY").ok()) else {
+        bail!("a --wallet-key or BALLOT_RELAYER_KEY is required");
+    };
+    let wallet = LocalWallet::from_str(&wallet_key)?;
+    let address = wallet.address();
+
+    let client = network.client(wallet)?;
+    let window = Duration::from_secs(args.window);
+    let relayer = Arc::new(Relayer {
+        client,
+        chain_id: network.chain_id,
+        ballots: args.ballots.into_iter().collect(),
+        voters: RateLimiter::new(args.voter_limit, window),
+        ips: RateLimiter::new(args.ip_limit, window),
+        in_flight: Mutex::default(),
+    });
+
+    let app = Router::new()
+        .route("/votes", post(relay_vote))
+        .layer(DefaultBodyLimit::max(MAX_REQUEST_BYTES))
+        .with_state(relayer);
+    let listener = TcpListener::bind(args.listen).await?;
+    eprintln!("Relaying votes on {} from {address:?} at http://{}", network.name, args.listen);
+    axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>()).await?;
+    Ok(())
+}
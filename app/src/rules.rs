@@ -0,0 +1,146 @@
+//! Client side ballot validation.
+//!
+//! The contract only checks that a ballot has one vote per proposal; it adds up whatever it is
+//! sent. A [`VotingRule`] describes which plaintext ballots are actually valid, so they can be
+//! rejected before anything is encrypted or sent.
+
+use crate::BallotError;
+
+/// Which vote vectors a ballot accepts.
+#[derive(Clone, Debug, PartialEq, Eq, uniffi::Enum)]
+pub enum VotingRule {
+    /// Exactly one proposal gets a vote of 1, every other proposal gets 0.
+    SingleChoice,
+    /// Each proposal gets a vote of 0 or 1.
+    Approval,
+    /// Any number of votes per proposal, up to `max_total` across the whole ballot.
+    Budgeted { max_total: u64 },
+    /// Casting `n` votes for a proposal costs `n²` credits, up to `credits` across the whole
+    /// ballot.
+    Quadratic { credits: u64 },
+}
+
+impl VotingRule {
+    /// Check `votes` against this rule for a ballot with `proposal_count` proposals.
+    pub fn validate(&self, votes: &[u64], proposal_count: usize) -> Result<(), BallotError> {
+        if votes.len() != proposal_count {
+            return Err(BallotError::invalid_vote(format!(
+                "ballot has {proposal_count} proposals but {} votes were given",
+                votes.len()
+            )));
+        }
+
+        match self {
+            Self::SingleChoice => {
+                if let Some(i) = votes.iter().position(|&vote| vote > 1) {
+                    return Err(BallotError::invalid_vote(format!(
+                        "single choice votes must be 0 or 1, proposal {i} got {}",
+                        votes[i]
+                    )));
+                }
+                let chosen = votes.iter().filter(|&&vote| vote == 1).count();
+                if chosen != 1 {
+                    return Err(BallotError::invalid_vote(format!(
+                        "single choice ballots vote for exactly one proposal, got {chosen}"
+                    )));
+                }
+            }
+            Self::Approval => {
+                if let Some(i) = votes.iter().position(|&vote| vote > 1) {
+                    return Err(BallotError::invalid_vote(format!(
+                        "approval votes must be 0 or 1, proposal {i} got {}",
+                        votes[i]
+                    )));
+                }
+            }
+            Self::Budgeted { max_total } => {
+                let total = total(votes.iter().copied())?;
+                if total > *max_total {
+                    return Err(BallotError::invalid_vote(format!(
+                        "ballot casts {total} votes but the budget is {max_total}"
+                    )));
+                }
+            }
+            Self::Quadratic { credits } => {
+                let squares = votes
+                    .iter()
+                    .map(|&vote| vote.checked_mul(vote))
+                    .collect::<Option<Vec<u64>>>()
+                    .ok_or_else(|| BallotError::invalid_vote("vote is too large to square"))?;
+                let cost = total(squares)?;
+                if cost > *credits {
+                    return Err(BallotError::invalid_vote(format!(
+                        "ballot costs {cost} credits but only {credits} are available"
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn total(votes: impl IntoIterator<Item = u64>) -> Result<u64, BallotError> {
+    votes
+        .into_iter()
+        .try_fold(0u64, u64::checked_add)
+        .ok_or_else(|| BallotError::invalid_vote("total number of votes overflows"))
+}
+
+/// Check `votes` against `rule` for a ballot with `proposal_count` proposals.
+#[uniffi::export]
+pub fn validate_votes(
+    rule: VotingRule,
+    votes: Vec<u64>,
+    proposal_count: u64,
+) -> Result<(), BallotError> {
+    rule.validate(&votes, proposal_count as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_invalid(result: Result<(), BallotError>) -> bool {
+        matches!(result, Err(BallotError::InvalidVote { .. }))
+    }
+
+    #[test]
+    fn vote_count_must_match_proposals() {
+        assert!(is_invalid(VotingRule::Approval.validate(&[1, 0], 3)));
+        assert!(VotingRule::Approval.validate(&[1, 0, 1], 3).is_ok());
+    }
+
+    #[test]
+    fn single_choice() {
+        let rule = VotingRule::SingleChoice;
+        assert!(rule.validate(&[0, 1, 0], 3).is_ok());
+        assert!(is_invalid(rule.validate(&[0, 0, 0], 3)));
+        assert!(is_invalid(rule.validate(&[1, 1, 0], 3)));
+        assert!(is_invalid(rule.validate(&[0, 2, 0], 3)));
+    }
+
+    #[test]
+    fn approval() {
+        let rule = VotingRule::Approval;
+        assert!(rule.validate(&[0, 0, 0], 3).is_ok());
+        assert!(rule.validate(&[1, 1, 1], 3).is_ok());
+        assert!(is_invalid(rule.validate(&[1, 2, 1], 3)));
+    }
+
+    #[test]
+    fn budgeted() {
+        let rule = VotingRule::Budgeted { max_total: 5 };
+        assert!(rule.validate(&[2, 3, 0], 3).is_ok());
+        assert!(is_invalid(rule.validate(&[2, 3, 1], 3)));
+        assert!(is_invalid(rule.validate(&[u64::MAX, 1], 2)));
+    }
+
+    #[test]
+    fn quadratic() {
+        let rule = VotingRule::Quadratic { credits: 9 };
+        assert!(rule.validate(&[2, 2, 1], 3).is_ok());
+        assert!(rule.validate(&[3, 0, 0], 3).is_ok());
+        assert!(is_invalid(rule.validate(&[3, 1, 0], 3)));
+        assert!(is_invalid(rule.validate(&[u64::MAX, 0], 2)));
+    }
+}
@@ -0,0 +1,95 @@
+//! The process-wide tokio runtime backing the async exports.
+//!
+//! UniFFI polls the exported futures on the host's executor, but ethers needs a tokio reactor
+//! underneath it. Rather than building a runtime per call, every export spawns its work onto one
+//! lazily started runtime that the host can size up front and shut down when it is done.
+
+use std::{future::Future, sync::Mutex, time::Duration};
+
+use tokio::{
+    runtime::{Builder, Handle, Runtime},
+    task::JoinHandle,
+};
+
+use crate::BallotError;
+
+/// How long [`shutdown_runtime`] waits for in-flight calls before abandoning them.
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+
+struct State {
+    runtime: Option<Runtime>,
+    /// `None` uses tokio's default of one worker per core.
+    worker_threads: Option<usize>,
+}
+
+static STATE: Mutex<State> = Mutex::new(State { runtime: None, worker_threads: None });
+
+/// Set the number of worker threads used by the shared runtime.
+///
+/// This must be called before the first async call (or after [`shutdown_runtime`]); it fails if
+/// the runtime is already running.
+#[uniffi::export]
+pub fn configure_runtime(worker_threads: u32) -> Result<(), BallotError> {
+    let mut state = STATE.lock().map_err(BallotError::runtime)?;
+    if state.runtime.is_some() {
+        return Err(BallotError::runtime("runtime is already running; shut it down first"));
+    }
+    state.worker_threads = Some(worker_threads.max(1) as usize);
+    Ok(())
+}
+
+/// Stop the shared runtime, waiting briefly for in-flight calls to finish.
+///
+/// Calls still running after the timeout fail with [`BallotError::Runtime`]. The next async call
+/// starts a fresh runtime.
+#[uniffi::export]
+pub fn shutdown_runtime() {
+    let runtime = match STATE.lock() {
+        Ok(mut state) => state.runtime.take(),
+        Err(poisoned) => poisoned.into_inner().runtime.take(),
+    };
+    if let Some(runtime) = runtime {
+        runtime.shutdown_timeout(SHUTDOWN_TIMEOUT);
+    }
+}
+
+/// Handle to the shared runtime, starting it if necessary.
+fn handle() -> Result<Handle, BallotError> {
+    let mut state = STATE.lock().map_err(BallotError::runtime)?;
+    if let Some(runtime) = &state.runtime {
+        return Ok(runtime.handle().clone());
+    }
+
+    let mut builder = Builder::new_multi_thread();
+    builder.enable_all().thread_name("sunscreen-ballot");
+    if let Some(worker_threads) = state.worker_threads {
+        builder.worker_threads(worker_threads);
+    }
+    let runtime = builder.build().map_err(BallotError::runtime)?;
+    let handle = runtime.handle().clone();
+    state.runtime = Some(runtime);
+    Ok(handle)
+}
+
+/// Run `future` on the shared runtime and wait for its result.
+///
+/// The returned future can be polled from any executor, which is what lets UniFFI drive it from
+/// the host side.
+pub(crate) async fn spawn<F, T>(future: F) -> Result<T, BallotError>
+where
+    F: Future<Output = Result<T, BallotError>> + Send + 'static,
+    T: Send + 'static,
+{
+    handle()?.spawn(future).await.map_err(BallotError::runtime)?
+}
+
+/// Run `future` on the shared runtime in the background.
+///
+/// Used for long lived work, like watching for events, that reports back through a callback
+/// rather than a return value. Abort the returned handle to stop it.
+pub(crate) fn spawn_detached<F>(future: F) -> Result<JoinHandle<()>, BallotError>
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    Ok(handle()?.spawn(future))
+}
@@ -0,0 +1,129 @@
+//! Decrypted tallies.
+//!
+//! Reading the tallies means having the contract re-encrypt every proposal's vote count under
+//! our key, then decrypting each one, which is slow with dozens of proposals on a phone. The
+//! decryption is spread across cores, and a [`TallyCache`] remembers what each vote count
+//! decrypted to, so a refresh only decrypts the tallies that changed.
+//!
+//! Re-encryption is randomised, so the same tally comes back as different bytes every time. The
+//! cache is keyed by the hash of the proposal's `voteCount` ciphertext on chain instead. Every
+//! vote replaces that ciphertext, so a cached tally is only ever reused for the exact vote count
+//! it was decrypted from.
+//!
+//! A [packed](crate::Packing) ballot has only its packed count to re-encrypt and decrypt, which
+//! is cached by the hash of `packedCount` in the same way, and every tally is unpacked from it.
+
+use std::{collections::HashMap, sync::Mutex};
+
+use ethers::{
+    types::{H256, U256},
+    utils::keccak256,
+};
+use rayon::prelude::*;
+use sunscreen::{types::bfv::Unsigned256, PrivateKey, PublicKey};
+use sunscreen_web3::AsBytes;
+
+use crate::{unpack_tallies, BallotBackend, BallotError, EncUint256};
+
+/// Decrypt `tallies` with `private_key`, in parallel, keeping their order.
+pub async fn decrypt_tallies(
+    tallies: Vec<EncUint256>,
+    private_key: &PrivateKey,
+) -> Result<Vec<Unsigned256>, BallotError> {
+    let private_key = private_key.clone();
+    tokio::task::spawn_blocking(move || {
+        tallies.par_iter().map(|tally| tally.decrypt(&private_key)).collect()
+    })
+    .await
+    .map_err(BallotError::runtime)?
+}
+
+/// Tallies already decrypted, by the hash of the vote count they came from.
+#[derive(Default)]
+pub struct TallyCache {
+    tallies: Mutex<HashMap<H256, Unsigned256>>,
+}
+
+impl TallyCache {
+    /// The ballot's tallies, one per proposal, decrypting only those not seen before.
+    ///
+    /// The caller is expected to have checked the FHE parameters; see
+    /// [`ParamsReport`](crate::ParamsReport).
+    pub async fn tallies(
+        &self,
+        backend: &dyn BallotBackend,
+        public_key: &PublicKey,
+        private_key: &PrivateKey,
+    ) -> Result<Vec<U256>, BallotError> {
+        let slot_bits = backend.slot_bits().await?;
+        let (vote_counts, proposal_count) = vote_count_hashes(backend, slot_bits).await?;
+        let tallies =
+            self.decrypt(backend, slot_bits, vote_counts, public_key, private_key).await?;
+        unpack_tallies(tallies, slot_bits, proposal_count)
+    }
+
+    /// Decrypt what `getProposalTallys` returns for `vote_counts`, as far as it isn't cached.
+    async fn decrypt(
+        &self,
+        backend: &dyn BallotBackend,
+        slot_bits: u8,
+        vote_counts: Vec<H256>,
+        public_key: &PublicKey,
+        private_key: &PrivateKey,
+    ) -> Result<Vec<Unsigned256>, BallotError> {
+        if let Some(tallies) = self.cached(&vote_counts)? {
+            return Ok(tallies);
+        }
+
+        let public_key_bytes = public_key.as_bytes().map_err(BallotError::serialization)?;
+        let encrypted = backend.get_proposal_tallys(public_key_bytes).await?;
+        // A vote in between means the tallies can't be matched up with the vote counts, so
+        // they're all decrypted and none are cached
+        if vote_count_hashes(backend, slot_bits).await?.0 != vote_counts {
+            return decrypt_tallies(encrypted, private_key).await;
+        }
+
+        let missing: Vec<_> = {
+            let cache = self.tallies.lock().map_err(BallotError::runtime)?;
+            vote_counts
+                .iter()
+                .zip(encrypted)
+                .filter(|(hash, _)| !cache.contains_key(hash))
+                .collect()
+        };
+        let (hashes, missing): (Vec<_>, Vec<_>) = missing.into_iter().unzip();
+        let decrypted = decrypt_tallies(missing, private_key).await?;
+
+        let mut cache = self.tallies.lock().map_err(BallotError::runtime)?;
+        cache.extend(hashes.into_iter().copied().zip(decrypted));
+        // Anything else is for a vote count that has since been replaced
+        cache.retain(|hash, _| vote_counts.contains(hash));
+        vote_counts
+            .iter()
+            .map(|hash| {
+                cache.get(hash).cloned().ok_or_else(|| BallotError::runtime("tally missing"))
+            })
+            .collect()
+    }
+
+    /// Every tally in `vote_counts`, if all of them are cached.
+    fn cached(&self, vote_counts: &[H256]) -> Result<Option<Vec<Unsigned256>>, BallotError> {
+        let cache = self.tallies.lock().map_err(BallotError::runtime)?;
+        Ok(vote_counts.iter().map(|hash| cache.get(hash).cloned()).collect())
+    }
+}
+
+/// The hash of each proposal's `voteCount` ciphertext in order, or of the packed count alone on
+/// a packed ballot, and how many proposals there are.
+async fn vote_count_hashes(
+    backend: &dyn BallotBackend,
+    slot_bits: u8,
+) -> Result<(Vec<H256>, usize), BallotError> {
+    let proposals = backend.get_proposals().await?;
+    let hashes = if slot_bits == 0 {
+        proposals.iter().map(|proposal| H256::from(keccak256(&proposal.vote_count))).collect()
+    } else {
+        vec![H256::from(keccak256(backend.packed_count().await?))]
+    };
+    Ok((hashes, proposals.len()))
+}
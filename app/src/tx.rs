@@ -0,0 +1,130 @@
+//! Transaction outcomes.
+//!
+//! Everything that sends a transaction waits for it to be mined and returns a [`TxOutcome`]
+//! rather than the raw receipt. Being mined doesn't mean it succeeded, and receipts don't say why
+//! a transaction reverted, so for a reverted one the transaction is replayed with `eth_call`
+//! against the state before its block to recover the reason. That replay can disagree with what
+//! happened if an earlier transaction in the same block changed the state it depends on.
+
+use std::fmt;
+
+use ethers::{
+    contract::EthError,
+    providers::{Middleware, MiddlewareError, PendingTransaction},
+    types::{transaction::eip2718::TypedTransaction, Bytes, TransactionReceipt, U256},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::BallotError;
+
+/// Whether a mined transaction succeeded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, uniffi::Enum)]
+pub enum TxStatus {
+    Success,
+    Reverted,
+}
+
+impl fmt::Display for TxStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let status = match self {
+            Self::Success => "success",
+            Self::Reverted => "reverted",
+        };
+        f.write_str(status)
+    }
+}
+
+/// A mined transaction, summarised from its receipt.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, uniffi::Record)]
+pub struct TxOutcome {
+    /// Transaction hash, as `0x` prefixed hex
+    pub tx_hash: String,
+    pub block_number: u64,
+    pub gas_used: u64,
+    /// Price paid per unit of gas, in wei, if the node reports it
+    pub effective_gas_price: Option<u64>,
+    pub status: TxStatus,
+    /// Why the transaction reverted, if it did and the reason could be recovered
+    pub revert_reason: Option<String>,
+    /// Address of the contract the transaction deployed, if any, as lower case hex without a `0x`
+    /// prefix
+    pub contract_address: Option<String>,
+}
+
+impl TxOutcome {
+    /// Summarise `receipt`, replaying the transaction on `client` for its revert reason if it
+    /// reverted.
+    pub async fn from_receipt<M: Middleware>(
+        client: &M,
+        receipt: TransactionReceipt,
+    ) -> Result<Self, BallotError> {
+        let block_number = receipt
+            .block_number
+            .ok_or_else(|| BallotError::rpc("the receipt has no block number"))?
+            .as_u64();
+        let status =
+            if receipt.status == Some(0.into()) { TxStatus::Reverted } else { TxStatus::Success };
+        let revert_reason = match status {
+            TxStatus::Reverted => replay(client, &receipt, block_number).await,
+            TxStatus::Success => None,
+        };
+        let effective_gas_price = receipt
+            .effective_gas_price
+            .map(|price| {
+                u64::try_from(price).map_err(|_| BallotError::rpc("gas price is out of range"))
+            })
+            .transpose()?;
+
+        Ok(Self {
+            tx_hash: format!("{:?}", receipt.transaction_hash),
+            block_number,
+            gas_used: receipt.gas_used.unwrap_or_default().as_u64(),
+            effective_gas_price,
+            status,
+            revert_reason,
+            contract_address: receipt.contract_address.map(|address| format!("{address:x}")),
+        })
+    }
+
+    /// Wait for `pending`, sent through `client`, to be mined, and summarise it.
+    pub async fn confirm<M: Middleware>(
+        client: &M,
+        pending: PendingTransaction<'_, M::Provider>,
+    ) -> Result<Self, BallotError> {
+        let receipt = pending
+            .await
+            .map_err(BallotError::rpc)?
+            .ok_or_else(|| BallotError::rpc("the transaction was dropped before it was mined"))?;
+        Self::from_receipt(client, receipt).await
+    }
+
+    pub fn is_success(&self) -> bool {
+        self.status == TxStatus::Success
+    }
+
+    /// What the transaction cost, in wei, if the gas price is known.
+    pub fn fee(&self) -> Option<U256> {
+        self.effective_gas_price.map(|price| U256::from(self.gas_used) * U256::from(price))
+    }
+}
+
+/// Best effort revert reason from a failed call's return data.
+pub(crate) fn revert_reason(data: &Bytes) -> String {
+    String::decode_with_selector(data).unwrap_or_else(|| format!("reverted with {data}"))
+}
+
+/// Replay a reverted transaction against the state before its block, for the revert reason.
+async fn replay<M: Middleware>(
+    client: &M,
+    receipt: &TransactionReceipt,
+    block_number: u64,
+) -> Option<String> {
+    let tx = client.get_transaction(receipt.transaction_hash).await.ok()??;
+    let call: TypedTransaction = (&tx).into();
+    match client.call(&call, Some(block_number.saturating_sub(1).into())).await {
+        // Succeeding here means it ran out of gas, or the block changed the state before it
+        Ok(_) if receipt.gas_used == Some(tx.gas) => Some("out of gas".into()),
+        Ok(_) => None,
+        Err(e) => e.as_error_response()?.as_revert_data().map(|data| revert_reason(&data)),
+    }
+}
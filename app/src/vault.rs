@@ -0,0 +1,167 @@
+//! Passphrase protected storage for secrets.
+//!
+//! A sealed blob is a fixed size header followed by the AEAD ciphertext:
+//!
+//! | offset | size | contents                                         |
+//! |--------|------|--------------------------------------------------|
+//! | 0      | 8    | magic, `BALLOTKS`                                |
+//! | 8      | 1    | format version, currently 1                      |
+//! | 9      | 4    | Argon2id memory cost in KiB (little endian)      |
+//! | 13     | 4    | Argon2id iterations (little endian)              |
+//! | 17     | 4    | Argon2id parallelism (little endian)             |
+//! | 21     | 16   | Argon2id salt                                    |
+//! | 37     | 24   | XChaCha20-Poly1305 nonce                         |
+//! | 61     | ..   | XChaCha20-Poly1305 ciphertext and tag            |
+//!
+//! The key is derived from the passphrase with Argon2id, and the whole header is authenticated as
+//! associated data, so tampering with the KDF parameters is detected just like tampering with the
+//! ciphertext. The tag can only be checked after the key is derived, though, so [`open`] refuses
+//! KDF parameters above [`MAX_M_COST`], [`MAX_T_COST`] and [`MAX_P_COST`] before running Argon2.
+
+use argon2::{Algorithm, Argon2, Params, Version};
+use chacha20poly1305::{
+    aead::{Aead, KeyInit, Payload},
+    XChaCha20Poly1305, XNonce,
+};
+use ethers::prelude::rand::{thread_rng, RngCore};
+use zeroize::Zeroizing;
+
+use crate::BallotError;
+
+const MAGIC: &[u8; 8] = b"BALLOTKS";
+const VERSION: u8 = 1;
+
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 24;
+const KEY_LEN: usize = 32;
+const HEADER_LEN: usize = MAGIC.len() + 1 + 4 * 3 + SALT_LEN + NONCE_LEN;
+
+/// Largest Argon2id memory cost, in KiB, [`open`] will derive a key with.
+const MAX_M_COST: u32 = Params::DEFAULT_M_COST * 4;
+/// Largest Argon2id iteration count [`open`] will derive a key with.
+const MAX_T_COST: u32 = Params::DEFAULT_T_COST * 4;
+/// Largest Argon2id parallelism [`open`] will derive a key with.
+const MAX_P_COST: u32 = Params::DEFAULT_P_COST * 4;
+
+/// Whether `blob` looks like something produced by [`seal`].
+pub(crate) fn is_sealed(blob: &[u8]) -> bool {
+    blob.starts_with(MAGIC)
+}
+
+/// Encrypt `plaintext` under `passphrase`.
+pub(crate) fn seal(plaintext: &[u8], passphrase: &str) -> Result<Vec<u8>, BallotError> {
+    let params = Params::default();
+    let mut salt = [0u8; SALT_LEN];
+    let mut nonce = [0u8; NONCE_LEN];
+    thread_rng().fill_bytes(&mut salt);
+    thread_rng().fill_bytes(&mut nonce);
+
+    let mut header = Vec::with_capacity(HEADER_LEN);
+    header.extend_from_slice(MAGIC);
+    header.push(VERSION);
+    header.extend_from_slice(&params.m_cost().to_le_bytes());
+    header.extend_from_slice(&params.t_cost().to_le_bytes());
+    header.extend_from_slice(&params.p_cost().to_le_bytes());
+    header.extend_from_slice(&salt);
+    header.extend_from_slice(&nonce);
+
+    let cipher = cipher(passphrase, &salt, params)?;
+    let ciphertext = cipher
+        .encrypt(&XNonce::from(nonce), Payload { msg: plaintext, aad: &header })
+        .map_err(|_| BallotError::passphrase("could not encrypt key store"))?;
+
+    header.extend_from_slice(&ciphertext);
+    Ok(header)
+}
+
+/// Decrypt a blob produced by [`seal`].
+pub(crate) fn open(blob: &[u8], passphrase: &str) -> Result<Zeroizing<Vec<u8>>, BallotError> {
+    if blob.len() < HEADER_LEN || !is_sealed(blob) {
+        return Err(BallotError::passphrase("not an encrypted key store"));
+    }
+    let (header, ciphertext) = blob.split_at(HEADER_LEN);
+    let version = header[MAGIC.len()];
+    if version != VERSION {
+        return Err(BallotError::passphrase(format!(
+            "unsupported key store version {version}; this build reads version {VERSION}"
+        )));
+    }
+
+    let mut rest = &header[MAGIC.len() + 1..];
+    let mut take = |len: usize| {
+        let (field, tail) = rest.split_at(len);
+        rest = tail;
+        field
+    };
+    let m_cost = u32::from_le_bytes(take(4).try_into().expect("4 byte field"));
+    let t_cost = u32::from_le_bytes(take(4).try_into().expect("4 byte field"));
+    let p_cost = u32::from_le_bytes(take(4).try_into().expect("4 byte field"));
+    let salt = take(SALT_LEN);
+    let nonce: [u8; NONCE_LEN] = take(NONCE_LEN).try_into().expect("nonce sized field");
+
+    // The header isn't authenticated until after the key is derived, so a hostile file could
+    // otherwise make us allocate gigabytes or spin for hours before the tag check fails.
+    if m_cost > MAX_M_COST || t_cost > MAX_T_COST || p_cost > MAX_P_COST {
+        return Err(BallotError::passphrase(format!(
+            "key store asks for Argon2id m_cost={m_cost} KiB, t_cost={t_cost}, p_cost={p_cost}; \
+             this build allows at most {MAX_M_COST} KiB, {MAX_T_COST} and {MAX_P_COST}"
+        )));
+    }
+
+    let params = Params::new(m_cost, t_cost, p_cost, Some(KEY_LEN))
+        .map_err(|e| BallotError::passphrase(format!("corrupt key store header: {e}")))?;
+    let cipher = cipher(passphrase, salt, params)?;
+    let plaintext = cipher
+        .decrypt(&XNonce::from(nonce), Payload { msg: ciphertext, aad: header })
+        .map_err(|_| BallotError::passphrase("wrong passphrase or corrupted key store"))?;
+    Ok(Zeroizing::new(plaintext))
+}
+
+fn cipher(passphrase: &str, salt: &[u8], params: Params) -> Result<XChaCha20Poly1305, BallotError> {
+    let mut key = Zeroizing::new([0u8; KEY_LEN]);
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(passphrase.as_bytes(), salt, key.as_mut())
+        .map_err(|e| BallotError::passphrase(format!("could not derive key: {e}")))?;
+    Ok(XChaCha20Poly1305::new(key.as_ref().into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let blob = seal(b"secret key", "hunter2").unwrap();
+        assert!(is_sealed(&blob));
+        assert_eq!(open(&blob, "hunter2").unwrap().as_slice(), b"secret key");
+    }
+
+    #[test]
+    fn wrong_passphrase() {
+        let blob = seal(b"secret key", "hunter2").unwrap();
+        assert!(open(&blob, "hunter3").is_err());
+    }
+
+    #[test]
+    fn tampered_ciphertext() {
+        let mut blob = seal(b"secret key", "hunter2").unwrap();
+        *blob.last_mut().unwrap() ^= 1;
+        assert!(open(&blob, "hunter2").is_err());
+    }
+
+    #[test]
+    fn tampered_header() {
+        let mut blob = seal(b"secret key", "hunter2").unwrap();
+        blob[HEADER_LEN - 1] ^= 1;
+        assert!(open(&blob, "hunter2").is_err());
+    }
+
+    #[test]
+    fn excessive_kdf_params() {
+        let mut blob = seal(b"secret key", "hunter2").unwrap();
+        let m_cost = MAGIC.len() + 1;
+        blob[m_cost..m_cost + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+        let err = open(&blob, "hunter2").unwrap_err();
+        assert!(err.to_string().contains("m_cost"), "{err}");
+    }
+}
@@ -14,7 +14,24 @@ pub mod ballot {
     fn __abi() -> ::ethers::core::abi::Abi {
         ::ethers::core::abi::ethabi::Contract {
             constructor: ::core::option::Option::Some(::ethers::core::abi::ethabi::Constructor {
-                inputs: ::std::vec![],
+                inputs: ::std::vec![
+                    ::ethers::core::abi::ethabi::Param {
+                        name: ::std::borrow::ToOwned::to_owned("voterRoot_"),
+                        kind: ::ethers::core::abi::ethabi::ParamType::FixedBytes(
+                            32usize,
+                        ),
+                        internal_type: ::core::option::Option::Some(
+                            ::std::borrow::ToOwned::to_owned("bytes32"),
+                        ),
+                    },
+                    ::ethers::core::abi::ethabi::Param {
+                        name: ::std::borrow::ToOwned::to_owned("slotBits_"),
+                        kind: ::ethers::core::abi::ethabi::ParamType::Uint(8usize),
+                        internal_type: ::core::option::Option::Some(
+                            ::std::borrow::ToOwned::to_owned("uint8"),
+                        ),
+                    },
+                ],
             }),
             functions: ::core::convert::From::from([
                 (
@@ -44,6 +61,53 @@ pub mod ballot {
                         },
                     ],
                 ),
+                (
+                    ::std::borrow::ToOwned::to_owned("changeVote"),
+                    ::std::vec![
+                        ::ethers::core::abi::ethabi::Function {
+                            name: ::std::borrow::ToOwned::to_owned("changeVote"),
+                            inputs: ::std::vec![
+                                ::ethers::core::abi::ethabi::Param {
+                                    name: ::std::borrow::ToOwned::to_owned("votes"),
+                                    kind: ::ethers::core::abi::ethabi::ParamType::Array(
+                                        ::std::boxed::Box::new(
+                                            ::ethers::core::abi::ethabi::ParamType::Bytes,
+                                        ),
+                                    ),
+                                    internal_type: ::core::option::Option::Some(
+                                        ::std::borrow::ToOwned::to_owned("bytes[]"),
+                                    ),
+                                },
+                                ::ethers::core::abi::ethabi::Param {
+                                    name: ::std::borrow::ToOwned::to_owned("proof"),
+                                    kind: ::ethers::core::abi::ethabi::ParamType::Array(
+                                        ::std::boxed::Box::new(
+                                            ::ethers::core::abi::ethabi::ParamType::FixedBytes(32usize),
+                                        ),
+                                    ),
+                                    internal_type: ::core::option::Option::Some(
+                                        ::std::borrow::ToOwned::to_owned("bytes32[]"),
+                                    ),
+                                },
+                            ],
+                            outputs: ::std::vec![],
+                            constant: ::core::option::Option::None,
+                            state_mutability: ::ethers::core::abi::ethabi::StateMutability::NonPayable,
+                        },
+                    ],
+                ),
+                (
+                    ::std::borrow::ToOwned::to_owned("close"),
+                    ::std::vec![
+                        ::ethers::core::abi::ethabi::Function {
+                            name: ::std::borrow::ToOwned::to_owned("close"),
+                            inputs: ::std::vec![],
+                            outputs: ::std::vec![],
+                            constant: ::core::option::Option::None,
+                            state_mutability: ::ethers::core::abi::ethabi::StateMutability::NonPayable,
+                        },
+                    ],
+                ),
                 (
                     ::std::borrow::ToOwned::to_owned("getProposalTallys"),
                     ::std::vec![
@@ -126,6 +190,209 @@ pub mod ballot {
                         },
                     ],
                 ),
+                (
+                    ::std::borrow::ToOwned::to_owned("getResults"),
+                    ::std::vec![
+                        ::ethers::core::abi::ethabi::Function {
+                            name: ::std::borrow::ToOwned::to_owned("getResults"),
+                            inputs: ::std::vec![],
+                            outputs: ::std::vec![
+                                ::ethers::core::abi::ethabi::Param {
+                                    name: ::std::string::String::new(),
+                                    kind: ::ethers::core::abi::ethabi::ParamType::Array(
+                                        ::std::boxed::Box::new(
+                                            ::ethers::core::abi::ethabi::ParamType::Uint(64usize),
+                                        ),
+                                    ),
+                                    internal_type: ::core::option::Option::Some(
+                                        ::std::borrow::ToOwned::to_owned("uint64[]"),
+                                    ),
+                                },
+                            ],
+                            constant: ::core::option::Option::None,
+                            state_mutability: ::ethers::core::abi::ethabi::StateMutability::View,
+                        },
+                    ],
+                ),
+                (
+                    ::std::borrow::ToOwned::to_owned("hasVoted"),
+                    ::std::vec![
+                        ::ethers::core::abi::ethabi::Function {
+                            name: ::std::borrow::ToOwned::to_owned("hasVoted"),
+                            inputs: ::std::vec![
+                                ::ethers::core::abi::ethabi::Param {
+                                    name: ::std::borrow::ToOwned::to_owned("voter"),
+                                    kind: ::ethers::core::abi::ethabi::ParamType::Address,
+                                    internal_type: ::core::option::Option::Some(
+                                        ::std::borrow::ToOwned::to_owned("address"),
+                                    ),
+                                },
+                            ],
+                            outputs: ::std::vec![
+                                ::ethers::core::abi::ethabi::Param {
+                                    name: ::std::string::String::new(),
+                                    kind: ::ethers::core::abi::ethabi::ParamType::Bool,
+                                    internal_type: ::core::option::Option::Some(
+                                        ::std::borrow::ToOwned::to_owned("bool"),
+                                    ),
+                                },
+                            ],
+                            constant: ::core::option::Option::None,
+                            state_mutability: ::ethers::core::abi::ethabi::StateMutability::View,
+                        },
+                    ],
+                ),
+                (
+                    ::std::borrow::ToOwned::to_owned("isEligible"),
+                    ::std::vec![
+                        ::ethers::core::abi::ethabi::Function {
+                            name: ::std::borrow::ToOwned::to_owned("isEligible"),
+                            inputs: ::std::vec![
+                                ::ethers::core::abi::ethabi::Param {
+                                    name: ::std::borrow::ToOwned::to_owned("voter"),
+                                    kind: ::ethers::core::abi::ethabi::ParamType::Address,
+                                    internal_type: ::core::option::Option::Some(
+                                        ::std::borrow::ToOwned::to_owned("address"),
+                                    ),
+                                },
+                                ::ethers::core::abi::ethabi::Param {
+                                    name: ::std::borrow::ToOwned::to_owned("proof"),
+                                    kind: ::ethers::core::abi::ethabi::ParamType::Array(
+                                        ::std::boxed::Box::new(
+                                            ::ethers::core::abi::ethabi::ParamType::FixedBytes(32usize),
+                                        ),
+                                    ),
+                                    internal_type: ::core::option::Option::Some(
+                                        ::std::borrow::ToOwned::to_owned("bytes32[]"),
+                                    ),
+                                },
+                            ],
+                            outputs: ::std::vec![
+                                ::ethers::core::abi::ethabi::Param {
+                                    name: ::std::string::String::new(),
+                                    kind: ::ethers::core::abi::ethabi::ParamType::Bool,
+                                    internal_type: ::core::option::Option::Some(
+                                        ::std::borrow::ToOwned::to_owned("bool"),
+                                    ),
+                                },
+                            ],
+                            constant: ::core::option::Option::None,
+                            state_mutability: ::ethers::core::abi::ethabi::StateMutability::View,
+                        },
+                    ],
+                ),
+                (
+                    ::std::borrow::ToOwned::to_owned("nonces"),
+                    ::std::vec![
+                        ::ethers::core::abi::ethabi::Function {
+                            name: ::std::borrow::ToOwned::to_owned("nonces"),
+                            inputs: ::std::vec![
+                                ::ethers::core::abi::ethabi::Param {
+                                    name: ::std::string::String::new(),
+                                    kind: ::ethers::core::abi::ethabi::ParamType::Address,
+                                    internal_type: ::core::option::Option::Some(
+                                        ::std::borrow::ToOwned::to_owned("address"),
+                                    ),
+                                },
+                            ],
+                            outputs: ::std::vec![
+                                ::ethers::core::abi::ethabi::Param {
+                                    name: ::std::string::String::new(),
+                                    kind: ::ethers::core::abi::ethabi::ParamType::Uint(
+                                        256usize,
+                                    ),
+                                    internal_type: ::core::option::Option::Some(
+                                        ::std::borrow::ToOwned::to_owned("uint256"),
+                                    ),
+                                },
+                            ],
+                            constant: ::core::option::Option::None,
+                            state_mutability: ::ethers::core::abi::ethabi::StateMutability::View,
+                        },
+                    ],
+                ),
+                (
+                    ::std::borrow::ToOwned::to_owned("open"),
+                    ::std::vec![
+                        ::ethers::core::abi::ethabi::Function {
+                            name: ::std::borrow::ToOwned::to_owned("open"),
+                            inputs: ::std::vec![
+                                ::ethers::core::abi::ethabi::Param {
+                                    name: ::std::borrow::ToOwned::to_owned("votingEnds_"),
+                                    kind: ::ethers::core::abi::ethabi::ParamType::Uint(
+                                        256usize,
+                                    ),
+                                    internal_type: ::core::option::Option::Some(
+                                        ::std::borrow::ToOwned::to_owned("uint256"),
+                                    ),
+                                },
+                            ],
+                            outputs: ::std::vec![],
+                            constant: ::core::option::Option::None,
+                            state_mutability: ::ethers::core::abi::ethabi::StateMutability::NonPayable,
+                        },
+                    ],
+                ),
+                (
+                    ::std::borrow::ToOwned::to_owned("organiser"),
+                    ::std::vec![
+                        ::ethers::core::abi::ethabi::Function {
+                            name: ::std::borrow::ToOwned::to_owned("organiser"),
+                            inputs: ::std::vec![],
+                            outputs: ::std::vec![
+                                ::ethers::core::abi::ethabi::Param {
+                                    name: ::std::string::String::new(),
+                                    kind: ::ethers::core::abi::ethabi::ParamType::Address,
+                                    internal_type: ::core::option::Option::Some(
+                                        ::std::borrow::ToOwned::to_owned("address"),
+                                    ),
+                                },
+                            ],
+                            constant: ::core::option::Option::None,
+                            state_mutability: ::ethers::core::abi::ethabi::StateMutability::View,
+                        },
+                    ],
+                ),
+                (
+                    ::std::borrow::ToOwned::to_owned("packedCount"),
+                    ::std::vec![
+                        ::ethers::core::abi::ethabi::Function {
+                            name: ::std::borrow::ToOwned::to_owned("packedCount"),
+                            inputs: ::std::vec![],
+                            outputs: ::std::vec![
+                                ::ethers::core::abi::ethabi::Param {
+                                    name: ::std::string::String::new(),
+                                    kind: ::ethers::core::abi::ethabi::ParamType::Bytes,
+                                    internal_type: ::core::option::Option::Some(
+                                        ::std::borrow::ToOwned::to_owned("bytes"),
+                                    ),
+                                },
+                            ],
+                            constant: ::core::option::Option::None,
+                            state_mutability: ::ethers::core::abi::ethabi::StateMutability::View,
+                        },
+                    ],
+                ),
+                (
+                    ::std::borrow::ToOwned::to_owned("phase"),
+                    ::std::vec![
+                        ::ethers::core::abi::ethabi::Function {
+                            name: ::std::borrow::ToOwned::to_owned("phase"),
+                            inputs: ::std::vec![],
+                            outputs: ::std::vec![
+                                ::ethers::core::abi::ethabi::Param {
+                                    name: ::std::string::String::new(),
+                                    kind: ::ethers::core::abi::ethabi::ParamType::Uint(8usize),
+                                    internal_type: ::core::option::Option::Some(
+                                        ::std::borrow::ToOwned::to_owned("enum Ballot.Phase"),
+                                    ),
+                                },
+                            ],
+                            constant: ::core::option::Option::None,
+                            state_mutability: ::ethers::core::abi::ethabi::StateMutability::View,
+                        },
+                    ],
+                ),
                 (
                     ::std::borrow::ToOwned::to_owned("proposals"),
                     ::std::vec![
@@ -170,6 +437,26 @@ pub mod ballot {
                         },
                     ],
                 ),
+                (
+                    ::std::borrow::ToOwned::to_owned("slotBits"),
+                    ::std::vec![
+                        ::ethers::core::abi::ethabi::Function {
+                            name: ::std::borrow::ToOwned::to_owned("slotBits"),
+                            inputs: ::std::vec![],
+                            outputs: ::std::vec![
+                                ::ethers::core::abi::ethabi::Param {
+                                    name: ::std::string::String::new(),
+                                    kind: ::ethers::core::abi::ethabi::ParamType::Uint(8usize),
+                                    internal_type: ::core::option::Option::Some(
+                                        ::std::borrow::ToOwned::to_owned("uint8"),
+                                    ),
+                                },
+                            ],
+                            constant: ::core::option::Option::None,
+                            state_mutability: ::ethers::core::abi::ethabi::StateMutability::View,
+                        },
+                    ],
+                ),
                 (
                     ::std::borrow::ToOwned::to_owned("vote"),
                     ::std::vec![
@@ -187,15 +474,211 @@ pub mod ballot {
                                         ::std::borrow::ToOwned::to_owned("bytes[]"),
                                     ),
                                 },
+                                ::ethers::core::abi::ethabi::Param {
+                                    name: ::std::borrow::ToOwned::to_owned("proof"),
+                                    kind: ::ethers::core::abi::ethabi::ParamType::Array(
+                                        ::std::boxed::Box::new(
+                                            ::ethers::core::abi::ethabi::ParamType::FixedBytes(32usize),
+                                        ),
+                                    ),
+                                    internal_type: ::core::option::Option::Some(
+                                        ::std::borrow::ToOwned::to_owned("bytes32[]"),
+                                    ),
+                                },
+                            ],
+                            outputs: ::std::vec![],
+                            constant: ::core::option::Option::None,
+                            state_mutability: ::ethers::core::abi::ethabi::StateMutability::NonPayable,
+                        },
+                    ],
+                ),
+                (
+                    ::std::borrow::ToOwned::to_owned("voteBySig"),
+                    ::std::vec![
+                        ::ethers::core::abi::ethabi::Function {
+                            name: ::std::borrow::ToOwned::to_owned("voteBySig"),
+                            inputs: ::std::vec![
+                                ::ethers::core::abi::ethabi::Param {
+                                    name: ::std::borrow::ToOwned::to_owned("voter"),
+                                    kind: ::ethers::core::abi::ethabi::ParamType::Address,
+                                    internal_type: ::core::option::Option::Some(
+                                        ::std::borrow::ToOwned::to_owned("address"),
+                                    ),
+                                },
+                                ::ethers::core::abi::ethabi::Param {
+                                    name: ::std::borrow::ToOwned::to_owned("votes"),
+                                    kind: ::ethers::core::abi::ethabi::ParamType::Array(
+                                        ::std::boxed::Box::new(
+                                            ::ethers::core::abi::ethabi::ParamType::Bytes,
+                                        ),
+                                    ),
+                                    internal_type: ::core::option::Option::Some(
+                                        ::std::borrow::ToOwned::to_owned("bytes[]"),
+                                    ),
+                                },
+                                ::ethers::core::abi::ethabi::Param {
+                                    name: ::std::borrow::ToOwned::to_owned("proof"),
+                                    kind: ::ethers::core::abi::ethabi::ParamType::Array(
+                                        ::std::boxed::Box::new(
+                                            ::ethers::core::abi::ethabi::ParamType::FixedBytes(32usize),
+                                        ),
+                                    ),
+                                    internal_type: ::core::option::Option::Some(
+                                        ::std::borrow::ToOwned::to_owned("bytes32[]"),
+                                    ),
+                                },
+                                ::ethers::core::abi::ethabi::Param {
+                                    name: ::std::borrow::ToOwned::to_owned("deadline"),
+                                    kind: ::ethers::core::abi::ethabi::ParamType::Uint(
+                                        256usize,
+                                    ),
+                                    internal_type: ::core::option::Option::Some(
+                                        ::std::borrow::ToOwned::to_owned("uint256"),
+                                    ),
+                                },
+                                ::ethers::core::abi::ethabi::Param {
+                                    name: ::std::borrow::ToOwned::to_owned("signature"),
+                                    kind: ::ethers::core::abi::ethabi::ParamType::Bytes,
+                                    internal_type: ::core::option::Option::Some(
+                                        ::std::borrow::ToOwned::to_owned("bytes"),
+                                    ),
+                                },
+                            ],
+                            outputs: ::std::vec![],
+                            constant: ::core::option::Option::None,
+                            state_mutability: ::ethers::core::abi::ethabi::StateMutability::NonPayable,
+                        },
+                    ],
+                ),
+                (
+                    ::std::borrow::ToOwned::to_owned("voterRoot"),
+                    ::std::vec![
+                        ::ethers::core::abi::ethabi::Function {
+                            name: ::std::borrow::ToOwned::to_owned("voterRoot"),
+                            inputs: ::std::vec![],
+                            outputs: ::std::vec![
+                                ::ethers::core::abi::ethabi::Param {
+                                    name: ::std::string::String::new(),
+                                    kind: ::ethers::core::abi::ethabi::ParamType::FixedBytes(
+                                        32usize,
+                                    ),
+                                    internal_type: ::core::option::Option::Some(
+                                        ::std::borrow::ToOwned::to_owned("bytes32"),
+                                    ),
+                                },
+                            ],
+                            constant: ::core::option::Option::None,
+                            state_mutability: ::ethers::core::abi::ethabi::StateMutability::View,
+                        },
+                    ],
+                ),
+                (
+                    ::std::borrow::ToOwned::to_owned("votingEnds"),
+                    ::std::vec![
+                        ::ethers::core::abi::ethabi::Function {
+                            name: ::std::borrow::ToOwned::to_owned("votingEnds"),
+                            inputs: ::std::vec![],
+                            outputs: ::std::vec![
+                                ::ethers::core::abi::ethabi::Param {
+                                    name: ::std::string::String::new(),
+                                    kind: ::ethers::core::abi::ethabi::ParamType::Uint(
+                                        256usize,
+                                    ),
+                                    internal_type: ::core::option::Option::Some(
+                                        ::std::borrow::ToOwned::to_owned("uint256"),
+                                    ),
+                                },
+                            ],
+                            constant: ::core::option::Option::None,
+                            state_mutability: ::ethers::core::abi::ethabi::StateMutability::View,
+                        },
+                    ],
+                ),
+            ]),
+            events: ::core::convert::From::from([
+                (
+                    ::std::borrow::ToOwned::to_owned("ProposalAdded"),
+                    ::std::vec![
+                        ::ethers::core::abi::ethabi::Event {
+                            name: ::std::borrow::ToOwned::to_owned("ProposalAdded"),
+                            inputs: ::std::vec![
+                                ::ethers::core::abi::ethabi::EventParam {
+                                    name: ::std::borrow::ToOwned::to_owned("index"),
+                                    kind: ::ethers::core::abi::ethabi::ParamType::Uint(
+                                        256usize,
+                                    ),
+                                    indexed: true,
+                                },
+                                ::ethers::core::abi::ethabi::EventParam {
+                                    name: ::std::borrow::ToOwned::to_owned("name"),
+                                    kind: ::ethers::core::abi::ethabi::ParamType::String,
+                                    indexed: false,
+                                },
+                            ],
+                            anonymous: false,
+                        },
+                    ],
+                ),
+                (
+                    ::std::borrow::ToOwned::to_owned("VoteCast"),
+                    ::std::vec![
+                        ::ethers::core::abi::ethabi::Event {
+                            name: ::std::borrow::ToOwned::to_owned("VoteCast"),
+                            inputs: ::std::vec![
+                                ::ethers::core::abi::ethabi::EventParam {
+                                    name: ::std::borrow::ToOwned::to_owned("voter"),
+                                    kind: ::ethers::core::abi::ethabi::ParamType::Address,
+                                    indexed: true,
+                                },
+                                ::ethers::core::abi::ethabi::EventParam {
+                                    name: ::std::borrow::ToOwned::to_owned("replaced"),
+                                    kind: ::ethers::core::abi::ethabi::ParamType::Bool,
+                                    indexed: false,
+                                },
+                            ],
+                            anonymous: false,
+                        },
+                    ],
+                ),
+                (
+                    ::std::borrow::ToOwned::to_owned("VotingClosed"),
+                    ::std::vec![
+                        ::ethers::core::abi::ethabi::Event {
+                            name: ::std::borrow::ToOwned::to_owned("VotingClosed"),
+                            inputs: ::std::vec![
+                                ::ethers::core::abi::ethabi::EventParam {
+                                    name: ::std::borrow::ToOwned::to_owned("results"),
+                                    kind: ::ethers::core::abi::ethabi::ParamType::Array(
+                                        ::std::boxed::Box::new(
+                                            ::ethers::core::abi::ethabi::ParamType::Uint(64usize),
+                                        ),
+                                    ),
+                                    indexed: false,
+                                },
+                            ],
+                            anonymous: false,
+                        },
+                    ],
+                ),
+                (
+                    ::std::borrow::ToOwned::to_owned("VotingOpened"),
+                    ::std::vec![
+                        ::ethers::core::abi::ethabi::Event {
+                            name: ::std::borrow::ToOwned::to_owned("VotingOpened"),
+                            inputs: ::std::vec![
+                                ::ethers::core::abi::ethabi::EventParam {
+                                    name: ::std::borrow::ToOwned::to_owned("votingEnds"),
+                                    kind: ::ethers::core::abi::ethabi::ParamType::Uint(
+                                        256usize,
+                                    ),
+                                    indexed: false,
+                                },
                             ],
-                            outputs: ::std::vec![],
-                            constant: ::core::option::Option::None,
-                            state_mutability: ::ethers::core::abi::ethabi::StateMutability::NonPayable,
+                            anonymous: false,
                         },
                     ],
                 ),
             ]),
-            events: ::std::collections::BTreeMap::new(),
             errors: ::std::collections::BTreeMap::new(),
             receive: false,
             fallback: false,
@@ -303,6 +786,22 @@ pub mod ballot {
                 .method_hash([35, 209, 201, 94], (name, contents))
                 .expect("method not found (this should never happen)")
         }
+        ///Calls the contract's `changeVote` (0x65e96957) function
+        pub fn change_vote(
+            &self,
+            votes: ::std::vec::Vec<::ethers::core::types::Bytes>,
+            proof: ::std::vec::Vec<[u8; 32]>,
+        ) -> ::ethers::contract::builders::ContractCall<M, ()> {
+            self.0
+                .method_hash([101, 233, 105, 87], (votes, proof))
+                .expect("method not found (this should never happen)")
+        }
+        ///Calls the contract's `close` (0x43d726d6) function
+        pub fn close(&self) -> ::ethers::contract::builders::ContractCall<M, ()> {
+            self.0
+                .method_hash([67, 215, 38, 214], ())
+                .expect("method not found (this should never happen)")
+        }
         ///Calls the contract's `getProposalTallys` (0xce160da0) function
         pub fn get_proposal_tallys(
             &self,
@@ -334,6 +833,79 @@ pub mod ballot {
                 .method_hash([46, 51, 68, 82], ())
                 .expect("method not found (this should never happen)")
         }
+        ///Calls the contract's `getResults` (0x4717f97c) function
+        pub fn get_results(
+            &self,
+        ) -> ::ethers::contract::builders::ContractCall<M, ::std::vec::Vec<u64>> {
+            self.0
+                .method_hash([71, 23, 249, 124], ())
+                .expect("method not found (this should never happen)")
+        }
+        ///Calls the contract's `hasVoted` (0x09eef43e) function
+        pub fn has_voted(
+            &self,
+            voter: ::ethers::core::types::Address,
+        ) -> ::ethers::contract::builders::ContractCall<M, bool> {
+            self.0
+                .method_hash([9, 238, 244, 62], voter)
+                .expect("method not found (this should never happen)")
+        }
+        ///Calls the contract's `isEligible` (0xc18b065e) function
+        pub fn is_eligible(
+            &self,
+            voter: ::ethers::core::types::Address,
+            proof: ::std::vec::Vec<[u8; 32]>,
+        ) -> ::ethers::contract::builders::ContractCall<M, bool> {
+            self.0
+                .method_hash([193, 139, 6, 94], (voter, proof))
+                .expect("method not found (this should never happen)")
+        }
+        ///Calls the contract's `nonces` (0x7ecebe00) function
+        pub fn nonces(
+            &self,
+            p0: ::ethers::core::types::Address,
+        ) -> ::ethers::contract::builders::ContractCall<M, ::ethers::core::types::U256> {
+            self.0
+                .method_hash([126, 206, 190, 0], p0)
+                .expect("method not found (this should never happen)")
+        }
+        ///Calls the contract's `open` (0x690e7c09) function
+        pub fn open(
+            &self,
+            voting_ends: ::ethers::core::types::U256,
+        ) -> ::ethers::contract::builders::ContractCall<M, ()> {
+            self.0
+                .method_hash([105, 14, 124, 9], voting_ends)
+                .expect("method not found (this should never happen)")
+        }
+        ///Calls the contract's `organiser` (0x7e537486) function
+        pub fn organiser(
+            &self,
+        ) -> ::ethers::contract::builders::ContractCall<
+            M,
+            ::ethers::core::types::Address,
+        > {
+            self.0
+                .method_hash([126, 83, 116, 134], ())
+                .expect("method not found (this should never happen)")
+        }
+        ///Calls the contract's `packedCount` (0x1c5051c3) function
+        pub fn packed_count(
+            &self,
+        ) -> ::ethers::contract::builders::ContractCall<
+            M,
+            ::ethers::core::types::Bytes,
+        > {
+            self.0
+                .method_hash([28, 80, 81, 195], ())
+                .expect("method not found (this should never happen)")
+        }
+        ///Calls the contract's `phase` (0xb1c9fe6e) function
+        pub fn phase(&self) -> ::ethers::contract::builders::ContractCall<M, u8> {
+            self.0
+                .method_hash([177, 201, 254, 110], ())
+                .expect("method not found (this should never happen)")
+        }
         ///Calls the contract's `proposals` (0x013cf08b) function
         pub fn proposals(
             &self,
@@ -346,15 +918,100 @@ pub mod ballot {
                 .method_hash([1, 60, 240, 139], p0)
                 .expect("method not found (this should never happen)")
         }
-        ///Calls the contract's `vote` (0x7aa54b68) function
+        ///Calls the contract's `slotBits` (0x3006286e) function
+        pub fn slot_bits(&self) -> ::ethers::contract::builders::ContractCall<M, u8> {
+            self.0
+                .method_hash([48, 6, 40, 110], ())
+                .expect("method not found (this should never happen)")
+        }
+        ///Calls the contract's `vote` (0x61276b3e) function
         pub fn vote(
             &self,
             votes: ::std::vec::Vec<::ethers::core::types::Bytes>,
+            proof: ::std::vec::Vec<[u8; 32]>,
+        ) -> ::ethers::contract::builders::ContractCall<M, ()> {
+            self.0
+                .method_hash([97, 39, 107, 62], (votes, proof))
+                .expect("method not found (this should never happen)")
+        }
+        ///Calls the contract's `voteBySig` (0x55f68113) function
+        pub fn vote_by_sig(
+            &self,
+            voter: ::ethers::core::types::Address,
+            votes: ::std::vec::Vec<::ethers::core::types::Bytes>,
+            proof: ::std::vec::Vec<[u8; 32]>,
+            deadline: ::ethers::core::types::U256,
+            signature: ::ethers::core::types::Bytes,
         ) -> ::ethers::contract::builders::ContractCall<M, ()> {
             self.0
-                .method_hash([122, 165, 75, 104], votes)
+                .method_hash(
+                    [85, 246, 129, 19],
+                    (voter, votes, proof, deadline, signature),
+                )
+                .expect("method not found (this should never happen)")
+        }
+        ///Calls the contract's `voterRoot` (0x6c03572f) function
+        pub fn voter_root(
+            &self,
+        ) -> ::ethers::contract::builders::ContractCall<M, [u8; 32]> {
+            self.0
+                .method_hash([108, 3, 87, 47], ())
+                .expect("method not found (this should never happen)")
+        }
+        ///Calls the contract's `votingEnds` (0x280ee4ee) function
+        pub fn voting_ends(
+            &self,
+        ) -> ::ethers::contract::builders::ContractCall<M, ::ethers::core::types::U256> {
+            self.0
+                .method_hash([40, 14, 228, 238], ())
                 .expect("method not found (this should never happen)")
         }
+        ///Gets the contract's `ProposalAdded` event
+        pub fn proposal_added_filter(
+            &self,
+        ) -> ::ethers::contract::builders::Event<
+            ::std::sync::Arc<M>,
+            M,
+            ProposalAddedFilter,
+        > {
+            self.0.event()
+        }
+        ///Gets the contract's `VoteCast` event
+        pub fn vote_cast_filter(
+            &self,
+        ) -> ::ethers::contract::builders::Event<
+            ::std::sync::Arc<M>,
+            M,
+            VoteCastFilter,
+        > {
+            self.0.event()
+        }
+        ///Gets the contract's `VotingClosed` event
+        pub fn voting_closed_filter(
+            &self,
+        ) -> ::ethers::contract::builders::Event<
+            ::std::sync::Arc<M>,
+            M,
+            VotingClosedFilter,
+        > {
+            self.0.event()
+        }
+        ///Gets the contract's `VotingOpened` event
+        pub fn voting_opened_filter(
+            &self,
+        ) -> ::ethers::contract::builders::Event<
+            ::std::sync::Arc<M>,
+            M,
+            VotingOpenedFilter,
+        > {
+            self.0.event()
+        }
+        /// Returns an `Event` builder for all the events of this contract.
+        pub fn events(
+            &self,
+        ) -> ::ethers::contract::builders::Event<::std::sync::Arc<M>, M, BallotEvents> {
+            self.0.event_with_filter(::core::default::Default::default())
+        }
     }
     impl<M: ::ethers::providers::Middleware> From<::ethers::contract::Contract<M>>
     for Ballot<M> {
@@ -362,7 +1019,244 @@ pub mod ballot {
             Self::new(contract.address(), contract.client())
         }
     }
-    ///Container type for all input parameters for the `addProposal` function with signature `addProposal(string,string)` and selector `0x23d1c95e`
+    #[derive(
+        Clone,
+        ::ethers::contract::EthEvent,
+        ::ethers::contract::EthDisplay,
+        Default,
+        Debug,
+        PartialEq,
+        Eq,
+        Hash
+    )]
+    #[ethevent(name = "ProposalAdded", abi = "ProposalAdded(uint256,string)")]
+    pub struct ProposalAddedFilter {
+        #[ethevent(indexed)]
+        pub index: ::ethers::core::types::U256,
+        pub name: ::std::string::String,
+    }
+    #[derive(
+        Clone,
+        ::ethers::contract::EthEvent,
+        ::ethers::contract::EthDisplay,
+        Default,
+        Debug,
+        PartialEq,
+        Eq,
+        Hash
+    )]
+    #[ethevent(name = "VoteCast", abi = "VoteCast(address,bool)")]
+    pub struct VoteCastFilter {
+        #[ethevent(indexed)]
+        pub voter: ::ethers::core::types::Address,
+        pub replaced: bool,
+    }
+    #[derive(
+        Clone,
+        ::ethers::contract::EthEvent,
+        ::ethers::contract::EthDisplay,
+        Default,
+        Debug,
+        PartialEq,
+        Eq,
+        Hash
+    )]
+    #[ethevent(name = "VotingClosed", abi = "VotingClosed(uint64[])")]
+    pub struct VotingClosedFilter {
+        pub results: ::std::vec::Vec<u64>,
+    }
+    #[derive(
+        Clone,
+        ::ethers::contract::EthEvent,
+        ::ethers::contract::EthDisplay,
+        Default,
+        Debug,
+        PartialEq,
+        Eq,
+        Hash
+    )]
+    #[ethevent(name = "VotingOpened", abi = "VotingOpened(uint256)")]
+    pub struct VotingOpenedFilter {
+        pub voting_ends: ::ethers::core::types::U256,
+    }
+    ///Container type for all of the contract's events
+    #[derive(Clone, ::ethers::contract::EthAbiType, Debug, PartialEq, Eq, Hash)]
+    pub enum BallotEvents {
+        ProposalAddedFilter(ProposalAddedFilter),
+        VoteCastFilter(VoteCastFilter),
+        VotingClosedFilter(VotingClosedFilter),
+        VotingOpenedFilter(VotingOpenedFilter),
+    }
+    impl ::ethers::contract::EthLogDecode for BallotEvents {
+        fn decode_log(
+            log: &::ethers::core::abi::RawLog,
+        ) -> ::core::result::Result<Self, ::ethers::core::abi::Error> {
+            if let Ok(decoded) = ProposalAddedFilter::decode_log(log) {
+                return Ok(BallotEvents::ProposalAddedFilter(decoded));
+            }
+            if let Ok(decoded) = VoteCastFilter::decode_log(log) {
+                return Ok(BallotEvents::VoteCastFilter(decoded));
+            }
+            if let Ok(decoded) = VotingClosedFilter::decode_log(log) {
+                return Ok(BallotEvents::VotingClosedFilter(decoded));
+            }
+            if let Ok(decoded) = VotingOpenedFilter::decode_log(log) {
+                return Ok(BallotEvents::VotingOpenedFilter(decoded));
+            }
+            Err(::ethers::core::abi::Error::InvalidData)
+        }
+    }
+    impl ::core::fmt::Display for BallotEvents {
+        fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+            match self {
+                Self::ProposalAddedFilter(element) => {
+                    ::core::fmt::Display::fmt(element, f)
+                }
+                Self::VoteCastFilter(element) => ::core::fmt::Display::fmt(element, f),
+                Self::VotingClosedFilter(element) => {
+                    ::core::fmt::Display::fmt(element, f)
+                }
+                Self::VotingOpenedFilter(element) => {
+                    ::core::fmt::Display::fmt(element, f)
+                }
+            }
+        }
+    }
+    impl ::core::convert::From<ProposalAddedFilter> for BallotEvents {
+        fn from(value: ProposalAddedFilter) -> Self {
+            Self::ProposalAddedFilter(value)
+        }
+    }
+    impl ::core::convert::From<VoteCastFilter> for BallotEvents {
+        fn from(value: VoteCastFilter) -> Self {
+            Self::VoteCastFilter(value)
+        }
+    }
+    impl ::core::convert::From<VotingClosedFilter> for BallotEvents {
+        fn from(value: VotingClosedFilter) -> Self {
+            Self::VotingClosedFilter(value)
+        }
+    }
+    impl ::core::convert::From<VotingOpenedFilter> for BallotEvents {
+        fn from(value: VotingOpenedFilter) -> Self {
+            Self::VotingOpenedFilter(value)
+        }
+    }
+    ///Container type for all input parameters for the `addProposal` function with signature `addProposal(string,string)` and selector `0x23d1c95e`
+    #[derive(
+        Clone,
+        ::ethers::contract::EthCall,
+        ::ethers::contract::EthDisplay,
+        Default,
+        Debug,
+        PartialEq,
+        Eq,
+        Hash
+    )]
+    #[ethcall(name = "addProposal", abi = "addProposal(string,string)")]
+    pub struct AddProposalCall {
+        pub name: ::std::string::String,
+        pub contents: ::std::string::String,
+    }
+    ///Container type for all input parameters for the `changeVote` function with signature `changeVote(bytes[],bytes32[])` and selector `0x65e96957`
+    #[derive(
+        Clone,
+        ::ethers::contract::EthCall,
+        ::ethers::contract::EthDisplay,
+        Default,
+        Debug,
+        PartialEq,
+        Eq,
+        Hash
+    )]
+    #[ethcall(name = "changeVote", abi = "changeVote(bytes[],bytes32[])")]
+    pub struct ChangeVoteCall {
+        pub votes: ::std::vec::Vec<::ethers::core::types::Bytes>,
+        pub proof: ::std::vec::Vec<[u8; 32]>,
+    }
+    ///Container type for all input parameters for the `close` function with signature `close()` and selector `0x43d726d6`
+    #[derive(
+        Clone,
+        ::ethers::contract::EthCall,
+        ::ethers::contract::EthDisplay,
+        Default,
+        Debug,
+        PartialEq,
+        Eq,
+        Hash
+    )]
+    #[ethcall(name = "close", abi = "close()")]
+    pub struct CloseCall;
+    ///Container type for all input parameters for the `getProposalTallys` function with signature `getProposalTallys(bytes)` and selector `0xce160da0`
+    #[derive(
+        Clone,
+        ::ethers::contract::EthCall,
+        ::ethers::contract::EthDisplay,
+        Default,
+        Debug,
+        PartialEq,
+        Eq,
+        Hash
+    )]
+    #[ethcall(name = "getProposalTallys", abi = "getProposalTallys(bytes)")]
+    pub struct GetProposalTallysCall {
+        pub reenc_public_key: ::ethers::core::types::Bytes,
+    }
+    ///Container type for all input parameters for the `getProposals` function with signature `getProposals()` and selector `0x62564c48`
+    #[derive(
+        Clone,
+        ::ethers::contract::EthCall,
+        ::ethers::contract::EthDisplay,
+        Default,
+        Debug,
+        PartialEq,
+        Eq,
+        Hash
+    )]
+    #[ethcall(name = "getProposals", abi = "getProposals()")]
+    pub struct GetProposalsCall;
+    ///Container type for all input parameters for the `getPublicKey` function with signature `getPublicKey()` and selector `0x2e334452`
+    #[derive(
+        Clone,
+        ::ethers::contract::EthCall,
+        ::ethers::contract::EthDisplay,
+        Default,
+        Debug,
+        PartialEq,
+        Eq,
+        Hash
+    )]
+    #[ethcall(name = "getPublicKey", abi = "getPublicKey()")]
+    pub struct GetPublicKeyCall;
+    ///Container type for all input parameters for the `getResults` function with signature `getResults()` and selector `0x4717f97c`
+    #[derive(
+        Clone,
+        ::ethers::contract::EthCall,
+        ::ethers::contract::EthDisplay,
+        Default,
+        Debug,
+        PartialEq,
+        Eq,
+        Hash
+    )]
+    #[ethcall(name = "getResults", abi = "getResults()")]
+    pub struct GetResultsCall;
+    ///Container type for all input parameters for the `hasVoted` function with signature `hasVoted(address)` and selector `0x09eef43e`
+    #[derive(
+        Clone,
+        ::ethers::contract::EthCall,
+        ::ethers::contract::EthDisplay,
+        Default,
+        Debug,
+        PartialEq,
+        Eq,
+        Hash
+    )]
+    #[ethcall(name = "hasVoted", abi = "hasVoted(address)")]
+    pub struct HasVotedCall {
+        pub voter: ::ethers::core::types::Address,
+    }
+    ///Container type for all input parameters for the `isEligible` function with signature `isEligible(address,bytes32[])` and selector `0xc18b065e`
     #[derive(
         Clone,
         ::ethers::contract::EthCall,
@@ -373,12 +1267,12 @@ pub mod ballot {
         Eq,
         Hash
     )]
-    #[ethcall(name = "addProposal", abi = "addProposal(string,string)")]
-    pub struct AddProposalCall {
-        pub name: ::std::string::String,
-        pub contents: ::std::string::String,
+    #[ethcall(name = "isEligible", abi = "isEligible(address,bytes32[])")]
+    pub struct IsEligibleCall {
+        pub voter: ::ethers::core::types::Address,
+        pub proof: ::std::vec::Vec<[u8; 32]>,
     }
-    ///Container type for all input parameters for the `getProposalTallys` function with signature `getProposalTallys(bytes)` and selector `0xce160da0`
+    ///Container type for all input parameters for the `nonces` function with signature `nonces(address)` and selector `0x7ecebe00`
     #[derive(
         Clone,
         ::ethers::contract::EthCall,
@@ -389,11 +1283,24 @@ pub mod ballot {
         Eq,
         Hash
     )]
-    #[ethcall(name = "getProposalTallys", abi = "getProposalTallys(bytes)")]
-    pub struct GetProposalTallysCall {
-        pub reenc_public_key: ::ethers::core::types::Bytes,
+    #[ethcall(name = "nonces", abi = "nonces(address)")]
+    pub struct NoncesCall(pub ::ethers::core::types::Address);
+    ///Container type for all input parameters for the `open` function with signature `open(uint256)` and selector `0x690e7c09`
+    #[derive(
+        Clone,
+        ::ethers::contract::EthCall,
+        ::ethers::contract::EthDisplay,
+        Default,
+        Debug,
+        PartialEq,
+        Eq,
+        Hash
+    )]
+    #[ethcall(name = "open", abi = "open(uint256)")]
+    pub struct OpenCall {
+        pub voting_ends: ::ethers::core::types::U256,
     }
-    ///Container type for all input parameters for the `getProposals` function with signature `getProposals()` and selector `0x62564c48`
+    ///Container type for all input parameters for the `organiser` function with signature `organiser()` and selector `0x7e537486`
     #[derive(
         Clone,
         ::ethers::contract::EthCall,
@@ -404,9 +1311,9 @@ pub mod ballot {
         Eq,
         Hash
     )]
-    #[ethcall(name = "getProposals", abi = "getProposals()")]
-    pub struct GetProposalsCall;
-    ///Container type for all input parameters for the `getPublicKey` function with signature `getPublicKey()` and selector `0x2e334452`
+    #[ethcall(name = "organiser", abi = "organiser()")]
+    pub struct OrganiserCall;
+    ///Container type for all input parameters for the `packedCount` function with signature `packedCount()` and selector `0x1c5051c3`
     #[derive(
         Clone,
         ::ethers::contract::EthCall,
@@ -417,8 +1324,21 @@ pub mod ballot {
         Eq,
         Hash
     )]
-    #[ethcall(name = "getPublicKey", abi = "getPublicKey()")]
-    pub struct GetPublicKeyCall;
+    #[ethcall(name = "packedCount", abi = "packedCount()")]
+    pub struct PackedCountCall;
+    ///Container type for all input parameters for the `phase` function with signature `phase()` and selector `0xb1c9fe6e`
+    #[derive(
+        Clone,
+        ::ethers::contract::EthCall,
+        ::ethers::contract::EthDisplay,
+        Default,
+        Debug,
+        PartialEq,
+        Eq,
+        Hash
+    )]
+    #[ethcall(name = "phase", abi = "phase()")]
+    pub struct PhaseCall;
     ///Container type for all input parameters for the `proposals` function with signature `proposals(uint256)` and selector `0x013cf08b`
     #[derive(
         Clone,
@@ -432,7 +1352,20 @@ pub mod ballot {
     )]
     #[ethcall(name = "proposals", abi = "proposals(uint256)")]
     pub struct ProposalsCall(pub ::ethers::core::types::U256);
-    ///Container type for all input parameters for the `vote` function with signature `vote(bytes[])` and selector `0x7aa54b68`
+    ///Container type for all input parameters for the `slotBits` function with signature `slotBits()` and selector `0x3006286e`
+    #[derive(
+        Clone,
+        ::ethers::contract::EthCall,
+        ::ethers::contract::EthDisplay,
+        Default,
+        Debug,
+        PartialEq,
+        Eq,
+        Hash
+    )]
+    #[ethcall(name = "slotBits", abi = "slotBits()")]
+    pub struct SlotBitsCall;
+    ///Container type for all input parameters for the `vote` function with signature `vote(bytes[],bytes32[])` and selector `0x61276b3e`
     #[derive(
         Clone,
         ::ethers::contract::EthCall,
@@ -443,19 +1376,82 @@ pub mod ballot {
         Eq,
         Hash
     )]
-    #[ethcall(name = "vote", abi = "vote(bytes[])")]
+    #[ethcall(name = "vote", abi = "vote(bytes[],bytes32[])")]
     pub struct VoteCall {
         pub votes: ::std::vec::Vec<::ethers::core::types::Bytes>,
+        pub proof: ::std::vec::Vec<[u8; 32]>,
+    }
+    ///Container type for all input parameters for the `voteBySig` function with signature `voteBySig(address,bytes[],bytes32[],uint256,bytes)` and selector `0x55f68113`
+    #[derive(
+        Clone,
+        ::ethers::contract::EthCall,
+        ::ethers::contract::EthDisplay,
+        Default,
+        Debug,
+        PartialEq,
+        Eq,
+        Hash
+    )]
+    #[ethcall(
+        name = "voteBySig",
+        abi = "voteBySig(address,bytes[],bytes32[],uint256,bytes)"
+    )]
+    pub struct VoteBySigCall {
+        pub voter: ::ethers::core::types::Address,
+        pub votes: ::std::vec::Vec<::ethers::core::types::Bytes>,
+        pub proof: ::std::vec::Vec<[u8; 32]>,
+        pub deadline: ::ethers::core::types::U256,
+        pub signature: ::ethers::core::types::Bytes,
     }
+    ///Container type for all input parameters for the `voterRoot` function with signature `voterRoot()` and selector `0x6c03572f`
+    #[derive(
+        Clone,
+        ::ethers::contract::EthCall,
+        ::ethers::contract::EthDisplay,
+        Default,
+        Debug,
+        PartialEq,
+        Eq,
+        Hash
+    )]
+    #[ethcall(name = "voterRoot", abi = "voterRoot()")]
+    pub struct VoterRootCall;
+    ///Container type for all input parameters for the `votingEnds` function with signature `votingEnds()` and selector `0x280ee4ee`
+    #[derive(
+        Clone,
+        ::ethers::contract::EthCall,
+        ::ethers::contract::EthDisplay,
+        Default,
+        Debug,
+        PartialEq,
+        Eq,
+        Hash
+    )]
+    #[ethcall(name = "votingEnds", abi = "votingEnds()")]
+    pub struct VotingEndsCall;
     ///Container type for all of the contract's call
     #[derive(Clone, ::ethers::contract::EthAbiType, Debug, PartialEq, Eq, Hash)]
     pub enum BallotCalls {
         AddProposal(AddProposalCall),
+        ChangeVote(ChangeVoteCall),
+        Close(CloseCall),
         GetProposalTallys(GetProposalTallysCall),
         GetProposals(GetProposalsCall),
         GetPublicKey(GetPublicKeyCall),
+        GetResults(GetResultsCall),
+        HasVoted(HasVotedCall),
+        IsEligible(IsEligibleCall),
+        Nonces(NoncesCall),
+        Open(OpenCall),
+        Organiser(OrganiserCall),
+        PackedCount(PackedCountCall),
+        Phase(PhaseCall),
         Proposals(ProposalsCall),
+        SlotBits(SlotBitsCall),
         Vote(VoteCall),
+        VoteBySig(VoteBySigCall),
+        VoterRoot(VoterRootCall),
+        VotingEnds(VotingEndsCall),
     }
     impl ::ethers::core::abi::AbiDecode for BallotCalls {
         fn decode(
@@ -466,6 +1462,14 @@ pub mod ballot {
                 = <AddProposalCall as ::ethers::core::abi::AbiDecode>::decode(data) {
                 return Ok(Self::AddProposal(decoded));
             }
+            if let Ok(decoded)
+                = <ChangeVoteCall as ::ethers::core::abi::AbiDecode>::decode(data) {
+                return Ok(Self::ChangeVote(decoded));
+            }
+            if let Ok(decoded)
+                = <CloseCall as ::ethers::core::abi::AbiDecode>::decode(data) {
+                return Ok(Self::Close(decoded));
+            }
             if let Ok(decoded)
                 = <GetProposalTallysCall as ::ethers::core::abi::AbiDecode>::decode(
                     data,
@@ -480,14 +1484,62 @@ pub mod ballot {
                 = <GetPublicKeyCall as ::ethers::core::abi::AbiDecode>::decode(data) {
                 return Ok(Self::GetPublicKey(decoded));
             }
+            if let Ok(decoded)
+                = <GetResultsCall as ::ethers::core::abi::AbiDecode>::decode(data) {
+                return Ok(Self::GetResults(decoded));
+            }
+            if let Ok(decoded)
+                = <HasVotedCall as ::ethers::core::abi::AbiDecode>::decode(data) {
+                return Ok(Self::HasVoted(decoded));
+            }
+            if let Ok(decoded)
+                = <IsEligibleCall as ::ethers::core::abi::AbiDecode>::decode(data) {
+                return Ok(Self::IsEligible(decoded));
+            }
+            if let Ok(decoded)
+                = <NoncesCall as ::ethers::core::abi::AbiDecode>::decode(data) {
+                return Ok(Self::Nonces(decoded));
+            }
+            if let Ok(decoded)
+                = <OpenCall as ::ethers::core::abi::AbiDecode>::decode(data) {
+                return Ok(Self::Open(decoded));
+            }
+            if let Ok(decoded)
+                = <OrganiserCall as ::ethers::core::abi::AbiDecode>::decode(data) {
+                return Ok(Self::Organiser(decoded));
+            }
+            if let Ok(decoded)
+                = <PackedCountCall as ::ethers::core::abi::AbiDecode>::decode(data) {
+                return Ok(Self::PackedCount(decoded));
+            }
+            if let Ok(decoded)
+                = <PhaseCall as ::ethers::core::abi::AbiDecode>::decode(data) {
+                return Ok(Self::Phase(decoded));
+            }
             if let Ok(decoded)
                 = <ProposalsCall as ::ethers::core::abi::AbiDecode>::decode(data) {
                 return Ok(Self::Proposals(decoded));
             }
+            if let Ok(decoded)
+                = <SlotBitsCall as ::ethers::core::abi::AbiDecode>::decode(data) {
+                return Ok(Self::SlotBits(decoded));
+            }
             if let Ok(decoded)
                 = <VoteCall as ::ethers::core::abi::AbiDecode>::decode(data) {
                 return Ok(Self::Vote(decoded));
             }
+            if let Ok(decoded)
+                = <VoteBySigCall as ::ethers::core::abi::AbiDecode>::decode(data) {
+                return Ok(Self::VoteBySig(decoded));
+            }
+            if let Ok(decoded)
+                = <VoterRootCall as ::ethers::core::abi::AbiDecode>::decode(data) {
+                return Ok(Self::VoterRoot(decoded));
+            }
+            if let Ok(decoded)
+                = <VotingEndsCall as ::ethers::core::abi::AbiDecode>::decode(data) {
+                return Ok(Self::VotingEnds(decoded));
+            }
             Err(::ethers::core::abi::Error::InvalidData.into())
         }
     }
@@ -497,6 +1549,10 @@ pub mod ballot {
                 Self::AddProposal(element) => {
                     ::ethers::core::abi::AbiEncode::encode(element)
                 }
+                Self::ChangeVote(element) => {
+                    ::ethers::core::abi::AbiEncode::encode(element)
+                }
+                Self::Close(element) => ::ethers::core::abi::AbiEncode::encode(element),
                 Self::GetProposalTallys(element) => {
                     ::ethers::core::abi::AbiEncode::encode(element)
                 }
@@ -506,10 +1562,40 @@ pub mod ballot {
                 Self::GetPublicKey(element) => {
                     ::ethers::core::abi::AbiEncode::encode(element)
                 }
+                Self::GetResults(element) => {
+                    ::ethers::core::abi::AbiEncode::encode(element)
+                }
+                Self::HasVoted(element) => {
+                    ::ethers::core::abi::AbiEncode::encode(element)
+                }
+                Self::IsEligible(element) => {
+                    ::ethers::core::abi::AbiEncode::encode(element)
+                }
+                Self::Nonces(element) => ::ethers::core::abi::AbiEncode::encode(element),
+                Self::Open(element) => ::ethers::core::abi::AbiEncode::encode(element),
+                Self::Organiser(element) => {
+                    ::ethers::core::abi::AbiEncode::encode(element)
+                }
+                Self::PackedCount(element) => {
+                    ::ethers::core::abi::AbiEncode::encode(element)
+                }
+                Self::Phase(element) => ::ethers::core::abi::AbiEncode::encode(element),
                 Self::Proposals(element) => {
                     ::ethers::core::abi::AbiEncode::encode(element)
                 }
+                Self::SlotBits(element) => {
+                    ::ethers::core::abi::AbiEncode::encode(element)
+                }
                 Self::Vote(element) => ::ethers::core::abi::AbiEncode::encode(element),
+                Self::VoteBySig(element) => {
+                    ::ethers::core::abi::AbiEncode::encode(element)
+                }
+                Self::VoterRoot(element) => {
+                    ::ethers::core::abi::AbiEncode::encode(element)
+                }
+                Self::VotingEnds(element) => {
+                    ::ethers::core::abi::AbiEncode::encode(element)
+                }
             }
         }
     }
@@ -517,11 +1603,25 @@ pub mod ballot {
         fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
             match self {
                 Self::AddProposal(element) => ::core::fmt::Display::fmt(element, f),
+                Self::ChangeVote(element) => ::core::fmt::Display::fmt(element, f),
+                Self::Close(element) => ::core::fmt::Display::fmt(element, f),
                 Self::GetProposalTallys(element) => ::core::fmt::Display::fmt(element, f),
                 Self::GetProposals(element) => ::core::fmt::Display::fmt(element, f),
                 Self::GetPublicKey(element) => ::core::fmt::Display::fmt(element, f),
+                Self::GetResults(element) => ::core::fmt::Display::fmt(element, f),
+                Self::HasVoted(element) => ::core::fmt::Display::fmt(element, f),
+                Self::IsEligible(element) => ::core::fmt::Display::fmt(element, f),
+                Self::Nonces(element) => ::core::fmt::Display::fmt(element, f),
+                Self::Open(element) => ::core::fmt::Display::fmt(element, f),
+                Self::Organiser(element) => ::core::fmt::Display::fmt(element, f),
+                Self::PackedCount(element) => ::core::fmt::Display::fmt(element, f),
+                Self::Phase(element) => ::core::fmt::Display::fmt(element, f),
                 Self::Proposals(element) => ::core::fmt::Display::fmt(element, f),
+                Self::SlotBits(element) => ::core::fmt::Display::fmt(element, f),
                 Self::Vote(element) => ::core::fmt::Display::fmt(element, f),
+                Self::VoteBySig(element) => ::core::fmt::Display::fmt(element, f),
+                Self::VoterRoot(element) => ::core::fmt::Display::fmt(element, f),
+                Self::VotingEnds(element) => ::core::fmt::Display::fmt(element, f),
             }
         }
     }
@@ -530,6 +1630,16 @@ pub mod ballot {
             Self::AddProposal(value)
         }
     }
+    impl ::core::convert::From<ChangeVoteCall> for BallotCalls {
+        fn from(value: ChangeVoteCall) -> Self {
+            Self::ChangeVote(value)
+        }
+    }
+    impl ::core::convert::From<CloseCall> for BallotCalls {
+        fn from(value: CloseCall) -> Self {
+            Self::Close(value)
+        }
+    }
     impl ::core::convert::From<GetProposalTallysCall> for BallotCalls {
         fn from(value: GetProposalTallysCall) -> Self {
             Self::GetProposalTallys(value)
@@ -545,16 +1655,76 @@ pub mod ballot {
             Self::GetPublicKey(value)
         }
     }
+    impl ::core::convert::From<GetResultsCall> for BallotCalls {
+        fn from(value: GetResultsCall) -> Self {
+            Self::GetResults(value)
+        }
+    }
+    impl ::core::convert::From<HasVotedCall> for BallotCalls {
+        fn from(value: HasVotedCall) -> Self {
+            Self::HasVoted(value)
+        }
+    }
+    impl ::core::convert::From<IsEligibleCall> for BallotCalls {
+        fn from(value: IsEligibleCall) -> Self {
+            Self::IsEligible(value)
+        }
+    }
+    impl ::core::convert::From<NoncesCall> for BallotCalls {
+        fn from(value: NoncesCall) -> Self {
+            Self::Nonces(value)
+        }
+    }
+    impl ::core::convert::From<OpenCall> for BallotCalls {
+        fn from(value: OpenCall) -> Self {
+            Self::Open(value)
+        }
+    }
+    impl ::core::convert::From<OrganiserCall> for BallotCalls {
+        fn from(value: OrganiserCall) -> Self {
+            Self::Organiser(value)
+        }
+    }
+    impl ::core::convert::From<PackedCountCall> for BallotCalls {
+        fn from(value: PackedCountCall) -> Self {
+            Self::PackedCount(value)
+        }
+    }
+    impl ::core::convert::From<PhaseCall> for BallotCalls {
+        fn from(value: PhaseCall) -> Self {
+            Self::Phase(value)
+        }
+    }
     impl ::core::convert::From<ProposalsCall> for BallotCalls {
         fn from(value: ProposalsCall) -> Self {
             Self::Proposals(value)
         }
     }
+    impl ::core::convert::From<SlotBitsCall> for BallotCalls {
+        fn from(value: SlotBitsCall) -> Self {
+            Self::SlotBits(value)
+        }
+    }
     impl ::core::convert::From<VoteCall> for BallotCalls {
         fn from(value: VoteCall) -> Self {
             Self::Vote(value)
         }
     }
+    impl ::core::convert::From<VoteBySigCall> for BallotCalls {
+        fn from(value: VoteBySigCall) -> Self {
+            Self::VoteBySig(value)
+        }
+    }
+    impl ::core::convert::From<VoterRootCall> for BallotCalls {
+        fn from(value: VoterRootCall) -> Self {
+            Self::VoterRoot(value)
+        }
+    }
+    impl ::core::convert::From<VotingEndsCall> for BallotCalls {
+        fn from(value: VotingEndsCall) -> Self {
+            Self::VotingEnds(value)
+        }
+    }
     ///Container type for all return fields from the `getProposalTallys` function with signature `getProposalTallys(bytes)` and selector `0xce160da0`
     #[derive(
         Clone,
@@ -593,6 +1763,90 @@ pub mod ballot {
         Hash
     )]
     pub struct GetPublicKeyReturn(pub ::ethers::core::types::Bytes);
+    ///Container type for all return fields from the `getResults` function with signature `getResults()` and selector `0x4717f97c`
+    #[derive(
+        Clone,
+        ::ethers::contract::EthAbiType,
+        ::ethers::contract::EthAbiCodec,
+        Default,
+        Debug,
+        PartialEq,
+        Eq,
+        Hash
+    )]
+    pub struct GetResultsReturn(pub ::std::vec::Vec<u64>);
+    ///Container type for all return fields from the `hasVoted` function with signature `hasVoted(address)` and selector `0x09eef43e`
+    #[derive(
+        Clone,
+        ::ethers::contract::EthAbiType,
+        ::ethers::contract::EthAbiCodec,
+        Default,
+        Debug,
+        PartialEq,
+        Eq,
+        Hash
+    )]
+    pub struct HasVotedReturn(pub bool);
+    ///Container type for all return fields from the `isEligible` function with signature `isEligible(address,bytes32[])` and selector `0xc18b065e`
+    #[derive(
+        Clone,
+        ::ethers::contract::EthAbiType,
+        ::ethers::contract::EthAbiCodec,
+        Default,
+        Debug,
+        PartialEq,
+        Eq,
+        Hash
+    )]
+    pub struct IsEligibleReturn(pub bool);
+    ///Container type for all return fields from the `nonces` function with signature `nonces(address)` and selector `0x7ecebe00`
+    #[derive(
+        Clone,
+        ::ethers::contract::EthAbiType,
+        ::ethers::contract::EthAbiCodec,
+        Default,
+        Debug,
+        PartialEq,
+        Eq,
+        Hash
+    )]
+    pub struct NoncesReturn(pub ::ethers::core::types::U256);
+    ///Container type for all return fields from the `organiser` function with signature `organiser()` and selector `0x7e537486`
+    #[derive(
+        Clone,
+        ::ethers::contract::EthAbiType,
+        ::ethers::contract::EthAbiCodec,
+        Default,
+        Debug,
+        PartialEq,
+        Eq,
+        Hash
+    )]
+    pub struct OrganiserReturn(pub ::ethers::core::types::Address);
+    ///Container type for all return fields from the `packedCount` function with signature `packedCount()` and selector `0x1c5051c3`
+    #[derive(
+        Clone,
+        ::ethers::contract::EthAbiType,
+        ::ethers::contract::EthAbiCodec,
+        Default,
+        Debug,
+        PartialEq,
+        Eq,
+        Hash
+    )]
+    pub struct PackedCountReturn(pub ::ethers::core::types::Bytes);
+    ///Container type for all return fields from the `phase` function with signature `phase()` and selector `0xb1c9fe6e`
+    #[derive(
+        Clone,
+        ::ethers::contract::EthAbiType,
+        ::ethers::contract::EthAbiCodec,
+        Default,
+        Debug,
+        PartialEq,
+        Eq,
+        Hash
+    )]
+    pub struct PhaseReturn(pub u8);
     ///Container type for all return fields from the `proposals` function with signature `proposals(uint256)` and selector `0x013cf08b`
     #[derive(
         Clone,
@@ -609,6 +1863,42 @@ pub mod ballot {
         pub contents: ::std::string::String,
         pub vote_count: ::ethers::core::types::Bytes,
     }
+    ///Container type for all return fields from the `slotBits` function with signature `slotBits()` and selector `0x3006286e`
+    #[derive(
+        Clone,
+        ::ethers::contract::EthAbiType,
+        ::ethers::contract::EthAbiCodec,
+        Default,
+        Debug,
+        PartialEq,
+        Eq,
+        Hash
+    )]
+    pub struct SlotBitsReturn(pub u8);
+    ///Container type for all return fields from the `voterRoot` function with signature `voterRoot()` and selector `0x6c03572f`
+    #[derive(
+        Clone,
+        ::ethers::contract::EthAbiType,
+        ::ethers::contract::EthAbiCodec,
+        Default,
+        Debug,
+        PartialEq,
+        Eq,
+        Hash
+    )]
+    pub struct VoterRootReturn(pub [u8; 32]);
+    ///Container type for all return fields from the `votingEnds` function with signature `votingEnds()` and selector `0x280ee4ee`
+    #[derive(
+        Clone,
+        ::ethers::contract::EthAbiType,
+        ::ethers::contract::EthAbiCodec,
+        Default,
+        Debug,
+        PartialEq,
+        Eq,
+        Hash
+    )]
+    pub struct VotingEndsReturn(pub ::ethers::core::types::U256);
     ///`Proposal(string,string,bytes)`
     #[derive(
         Clone,
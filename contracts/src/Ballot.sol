@@ -1,9 +1,20 @@
 // SPDX-License-Identifier: UNLICENSED
 pragma solidity ^0.8.19;
 
+import "openzeppelin/contracts/utils/cryptography/ECDSA.sol";
+import "openzeppelin/contracts/utils/cryptography/EIP712.sol";
+import "openzeppelin/contracts/utils/cryptography/MerkleProof.sol";
 import "sunscreen/src/FHE.sol";
 
-contract Ballot {
+contract Ballot is EIP712 {
+    // Proposals are added during Setup, votes cast during Voting, and tallies revealed on
+    // entering Closed
+    enum Phase {
+        Setup,
+        Voting,
+        Closed
+    }
+
     struct Proposal {
         string name;
         string contents;
@@ -11,15 +22,52 @@ contract Ballot {
     }
 
     struct Voter {
+        bool voted;
         bytes[] votes;
     }
 
+    event ProposalAdded(uint256 indexed index, string name);
+    event VoteCast(address indexed voter, bool replaced);
+    event VotingOpened(uint256 votingEnds);
+    event VotingClosed(uint64[] results);
+
     mapping(address => Voter) private voters;
     FHE fhe;
     Proposal[] public proposals;
 
-    constructor() {
+    address public organiser;
+    Phase public phase;
+    uint256 public votingEnds;
+    uint64[] private results;
+
+    // Merkle root of the addresses allowed to vote, or zero to let anyone vote
+    bytes32 public voterRoot;
+
+    // Bits per proposal in a packed ballot, or zero if each proposal keeps its own count. A
+    // packed ballot keeps every count in one Unsigned256, proposal i in bits
+    // [i * slotBits, (i + 1) * slotBits), so a vote is one ciphertext and one addition however
+    // many proposals there are. Nothing stops a slot overflowing into the next, so slotBits has
+    // to fit the most votes a proposal can get.
+    uint8 public slotBits;
+    bytes public packedCount;
+
+    bytes32 private constant VOTE_TYPEHASH =
+        keccak256(
+            "Vote(address voter,bytes[] votes,uint256 nonce,uint256 deadline)"
+        );
+
+    // Each signed vote uses up the voter's next nonce, so it can't be replayed
+    mapping(address => uint256) public nonces;
+
+    constructor(bytes32 voterRoot_, uint8 slotBits_) EIP712("Ballot", "1") {
+        require(slotBits_ <= 64, "Slots can be at most 64 bits");
         fhe = new FHE();
+        organiser = msg.sender;
+        voterRoot = voterRoot_;
+        slotBits = slotBits_;
+        if (slotBits_ > 0) {
+            packedCount = fhe.encryptUint256(0);
+        }
     }
 
     function getPublicKey() public view returns (bytes memory) {
@@ -30,30 +78,195 @@ contract Ballot {
         return proposals;
     }
 
+    function hasVoted(address voter) public view returns (bool) {
+        return voters[voter].voted;
+    }
+
+    // Leaves are double hashed so no inner node can pass for a leaf
+    function isEligible(
+        address voter,
+        bytes32[] memory proof
+    ) public view returns (bool) {
+        if (voterRoot == bytes32(0)) {
+            return true;
+        }
+        bytes32 leaf = keccak256(bytes.concat(keccak256(abi.encode(voter))));
+        return MerkleProof.verify(proof, voterRoot, leaf);
+    }
+
+    function getResults() public view returns (uint64[] memory) {
+        require(phase == Phase.Closed, "Voting has not closed");
+        return results;
+    }
+
     function addProposal(string memory name, string memory contents) public {
-        bytes memory zero = fhe.encryptUint64(0);
+        require(msg.sender == organiser, "Only the organiser can add proposals");
+        _addProposal(name, contents);
+    }
+
+    // Adds several proposals in one transaction; all of them, or none if any can't be added
+    function addProposals(string[] memory names, string[] memory contents) public {
+        require(msg.sender == organiser, "Only the organiser can add proposals");
+        require(names.length == contents.length, "Every proposal needs a name and contents");
+        for (uint i = 0; i < names.length; i++) {
+            _addProposal(names[i], contents[i]);
+        }
+    }
+
+    function _addProposal(string memory name, string memory contents) internal {
+        require(phase == Phase.Setup, "Voting has already opened");
+        // A packed ballot's proposals have no count of their own
+        bytes memory zero;
+        if (slotBits == 0) {
+            zero = fhe.encryptUint64(0);
+        } else {
+            require(
+                (proposals.length + 1) * slotBits <= 256,
+                "No room in the packed count for another proposal"
+            );
+        }
         proposals.push(Proposal(name, contents, zero));
+        emit ProposalAdded(proposals.length - 1, name);
     }
 
-    function vote(bytes[] memory votes) public {
-        Voter storage sender = voters[msg.sender];
+    function open(uint256 votingEnds_) public {
+        require(msg.sender == organiser, "Only the organiser can open voting");
+        require(phase == Phase.Setup, "Voting has already opened");
+        require(proposals.length > 0, "There are no proposals to vote on");
+        require(votingEnds_ > block.timestamp, "Voting must end in the future");
+        phase = Phase.Voting;
+        votingEnds = votingEnds_;
+        emit VotingOpened(votingEnds_);
+    }
+
+    // The organiser can close early; once the deadline passes anyone can
+    function close() public {
+        require(phase == Phase.Voting, "Voting is not open");
         require(
-            votes.length == proposals.length,
-            "You need to give exactly as many votes as proposals"
+            msg.sender == organiser || block.timestamp >= votingEnds,
+            "Only the organiser can close voting before it ends"
         );
-        sender.votes = votes;
+        phase = Phase.Closed;
+        if (slotBits == 0) {
+            for (uint i = 0; i < proposals.length; i++) {
+                results.push(fhe.decryptUint64(proposals[i].voteCount));
+            }
+        } else {
+            uint256 packed = fhe.decryptUint256(packedCount);
+            uint256 mask = (1 << slotBits) - 1;
+            for (uint i = 0; i < proposals.length; i++) {
+                results.push(uint64((packed >> (i * slotBits)) & mask));
+            }
+        }
+        emit VotingClosed(results);
+    }
 
-        for (uint i = 0; i < proposals.length; i++) {
-            proposals[i].voteCount = fhe.addUint64EncEnc(
-                fhe.networkPublicKey(),
-                proposals[i].voteCount,
-                sender.votes[i]
+    // Casting a second vote replaces the first rather than adding to it
+    function vote(bytes[] memory votes, bytes32[] memory proof) public {
+        _vote(msg.sender, votes, proof);
+    }
+
+    function changeVote(bytes[] memory votes, bytes32[] memory proof) public {
+        require(voters[msg.sender].voted, "No vote to change");
+        _vote(msg.sender, votes, proof);
+    }
+
+    // Lets a relayer pay the gas for a vote the voter signed as EIP-712 typed data. The proof
+    // isn't signed; it can only ever show the voter's own eligibility.
+    function voteBySig(
+        address voter,
+        bytes[] memory votes,
+        bytes32[] memory proof,
+        uint256 deadline,
+        bytes memory signature
+    ) public {
+        require(block.timestamp <= deadline, "Signature expired");
+        bytes32[] memory voteHashes = new bytes32[](votes.length);
+        for (uint i = 0; i < votes.length; i++) {
+            voteHashes[i] = keccak256(votes[i]);
+        }
+        bytes32 structHash = keccak256(
+            abi.encode(
+                VOTE_TYPEHASH,
+                voter,
+                keccak256(abi.encodePacked(voteHashes)),
+                nonces[voter]++,
+                deadline
+            )
+        );
+        require(
+            ECDSA.recover(_hashTypedDataV4(structHash), signature) == voter,
+            "Invalid signature"
+        );
+        _vote(voter, votes, proof);
+    }
+
+    function _vote(
+        address voter,
+        bytes[] memory votes,
+        bytes32[] memory proof
+    ) internal {
+        require(
+            phase == Phase.Voting && block.timestamp < votingEnds,
+            "Voting is not open"
+        );
+        require(isEligible(voter, proof), "Not on the voter allowlist");
+        if (slotBits == 0) {
+            require(
+                votes.length == proposals.length,
+                "You need to give exactly as many votes as proposals"
             );
+        } else {
+            require(votes.length == 1, "Packed ballots take one packed vote");
+        }
+        Voter storage sender = voters[voter];
+        bytes memory pubk = fhe.networkPublicKey();
+        bool replaced = sender.voted;
+
+        if (slotBits > 0) {
+            if (replaced) {
+                packedCount = fhe.subtractUint256EncEnc(
+                    pubk,
+                    packedCount,
+                    sender.votes[0]
+                );
+            }
+            packedCount = fhe.addUint256EncEnc(pubk, packedCount, votes[0]);
+        } else {
+            if (replaced) {
+                // Proposals are fixed once voting opens, so the old vote covers every one
+                for (uint i = 0; i < sender.votes.length; i++) {
+                    proposals[i].voteCount = fhe.subtractUint64EncEnc(
+                        pubk,
+                        proposals[i].voteCount,
+                        sender.votes[i]
+                    );
+                }
+            }
+            for (uint i = 0; i < proposals.length; i++) {
+                proposals[i].voteCount = fhe.addUint64EncEnc(
+                    pubk,
+                    proposals[i].voteCount,
+                    votes[i]
+                );
+            }
         }
+
+        sender.voted = true;
+        sender.votes = votes;
+
+        emit VoteCast(voter, replaced);
     }
+
+    // A packed ballot has a single tally, the packed count
     function getProposalTallys(
         bytes calldata reencPublicKey
     ) public view returns (bytes[] memory) {
+        if (slotBits > 0) {
+            bytes[] memory packed = new bytes[](1);
+            packed[0] = fhe.reencryptUint256(reencPublicKey, packedCount);
+            return packed;
+        }
         bytes[] memory reEncProposals = new bytes[](proposals.length);
         for (uint i = 0; i < proposals.length; i++) {
             reEncProposals[i] = fhe.reencryptUint256(